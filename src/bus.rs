@@ -0,0 +1,103 @@
+use cart::Mapper;
+use MMU;
+
+// CPU cycles a bit of the PPU I/O latch holds on before decaying to 0, about 600 ms
+const PPU_LATCH_DECAY: u32 = 1_073_864;
+
+// CPU address space: 2K of RAM mirrored up to $1FFF and the cartridge from $4020
+pub struct Bus {
+    ram: [u8; 0x800],
+    pub cart: Box<dyn Mapper>,
+
+    // Last byte on the CPU data bus, which reads nothing drives return
+    open: u8,
+    // The PPU keeps its own data latch behind $2000-$3FFF, each bit fading on its own
+    ppu_latch: u8,
+    ppu_decay: [u32; 8],
+}
+
+impl Bus {
+    pub fn new(cart: Box<dyn Mapper>) -> Bus {
+        Bus {
+            ram: [0; 0x800],
+            cart,
+            open: 0,
+            ppu_latch: 0,
+            ppu_decay: [0; 8],
+        }
+    }
+
+    // Sets the bits of `mask` in the PPU latch, restarting their decay
+    fn refresh_ppu_latch(&mut self, v: u8, mask: u8) {
+        self.ppu_latch = (self.ppu_latch & !mask) | (v & mask);
+        (0..8)
+            .filter(|i| mask & (1 << i) != 0)
+            .for_each(|i| self.ppu_decay[i] = PPU_LATCH_DECAY);
+    }
+
+    // Bits the cartridge leaves floating keep the last value on the bus
+    fn drive(&self, a: u16, v: u8) -> u8 {
+        let m = self.cart.cpu_driven(a);
+        (v & m) | (self.open & !m)
+    }
+}
+
+impl MMU for Bus {
+    fn read(&mut self, a: u16) -> u8 {
+        let v = match a {
+            0x4020..=0xFFFF => {
+                let v = self.cart.cpu_read(a);
+                self.drive(a, v)
+            }
+            _ => self.peek(a),
+        };
+
+        self.open = v;
+        v
+    }
+
+    fn write(&mut self, a: u16, v: u8) {
+        self.open = v;
+
+        match a {
+            0x0000..=0x1FFF => self.ram[a as usize % 0x800] = v,
+            0x2000..=0x3FFF => {
+                self.refresh_ppu_latch(v, 0xFF);
+                self.cart.ppu_register(a & 0x2007, v)
+            }
+            0x8000..=0xFFFF if !self.cart.rom_registers() => (),
+            0x4020..=0xFFFF => self.cart.cpu_write(a, v),
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        for i in 0..8 {
+            if self.ppu_decay[i] > 0 {
+                self.ppu_decay[i] -= 1;
+                if self.ppu_decay[i] == 0 {
+                    self.ppu_latch &= !(1 << i);
+                }
+            }
+        }
+
+        self.cart.cycle();
+    }
+
+    fn peek(&self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.ram[a as usize % 0x800],
+            0x2000..=0x3FFF => self.ppu_latch,
+            0x4020..=0xFFFF => self.drive(a, self.cart.cpu_peek(a)),
+            _ => self.open,
+        }
+    }
+
+    fn fault(&mut self) -> Option<u16> {
+        self.cart.fault()
+    }
+
+    fn irq(&self) -> bool {
+        self.cart.irq()
+    }
+}
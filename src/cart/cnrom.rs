@@ -0,0 +1,108 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const CHR_BANK_SIZE: usize = 0x2000;
+
+// Mapper 3: fixed 16K or 32K of PRG and a switchable 8K CHR ROM bank
+pub struct CNROM {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
+    bus_conflicts: bool,
+    bank: u8,
+}
+
+impl CNROM {
+    pub fn new(d: &[u8]) -> Result<CNROM, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(CNROM::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> CNROM {
+        CNROM {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram: h.chr_rom_size == 0,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::trainer(h, d),
+            mirroring: h.mirroring,
+            bus_conflicts: h.bus_conflicts(),
+            bank: 0,
+        }
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((usize::from(self.bank) % banks) * CHR_BANK_SIZE + a as usize) % self.chr.len()
+    }
+}
+
+impl Mapper for CNROM {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF => self.prg_ram.get(a as usize - 0x6000).cloned().unwrap_or(0),
+            0x8000..=0xFFFF => self.prg_rom[(a as usize - 0x8000) % self.prg_rom.len()],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(b) = self.prg_ram.get_mut(a as usize - 0x6000) {
+                    *b = v
+                }
+            }
+            0x8000..=0xFFFF => self.bank = self.conflict(a, v),
+            _ => (),
+        }
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+
+    fn set_bus_conflicts(&mut self, on: bool) {
+        self.bus_conflicts = on;
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::fmt;
+
+use cart::header::HeaderError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CartError {
+    Header(HeaderError),
+    UnsupportedMapper { mapper: u16, submapper: u8 },
+    NoBattery,
+    SaveSize { expected: usize, found: usize },
+}
+
+impl fmt::Display for CartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::CartError::*;
+        match *self {
+            Header(e) => e.fmt(f),
+            UnsupportedMapper {
+                mapper,
+                submapper: 0,
+            } => write!(f, "Unsupported mapper {}", mapper),
+            UnsupportedMapper { mapper, submapper } => {
+                write!(f, "Unsupported mapper {}.{}", mapper, submapper)
+            }
+            NoBattery => write!(f, "Cartridge has no battery-backed RAM"),
+            SaveSize { expected, found } => write!(
+                f,
+                "Save is {} bytes where the cartridge has {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl Error for CartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            CartError::Header(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<HeaderError> for CartError {
+    fn from(e: HeaderError) -> CartError {
+        CartError::Header(e)
+    }
+}
@@ -0,0 +1,369 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// A channel at full volume is as loud as a full APU pulse channel
+const VOLUME: f32 = 0.1494;
+
+// #region Audio
+// Sunsoft 5B: three square channels of a YM2149F, with noise and the envelope
+struct Envelope {
+    period: u16,
+    shape: u8,
+    counter: u32,
+    step: u8, // 32 steps a cycle
+    attack: bool,
+    holding: bool,
+}
+
+impl Envelope {
+    fn new() -> Envelope {
+        Envelope {
+            period: 0,
+            shape: 0,
+            counter: 0,
+            step: 31,
+            attack: false,
+            holding: true,
+        }
+    }
+
+    fn set_shape(&mut self, v: u8) {
+        self.shape = v & 0x0F;
+        self.attack = v & 0x04 != 0;
+        self.step = 0;
+        self.counter = 0;
+        self.holding = false;
+    }
+
+    fn clock(&mut self) {
+        if self.holding {
+            return;
+        }
+        if self.counter > 0 {
+            self.counter -= 1;
+            return;
+        }
+
+        self.counter = u32::from(self.period.max(1)) * 8 - 1;
+        self.step += 1;
+        if self.step < 32 {
+            return;
+        }
+
+        // Shapes without the continue bit drop to 0 and stay there
+        let (cont, alternate, hold) = (self.shape & 0x08, self.shape & 0x02, self.shape & 0x01);
+        if cont == 0 {
+            self.attack = false;
+            self.holding = true;
+            self.step = 31;
+        } else {
+            if alternate != 0 {
+                self.attack = !self.attack;
+            }
+            self.holding = hold != 0;
+            self.step = if self.holding { 31 } else { 0 };
+        }
+    }
+
+    fn level(&self) -> u8 {
+        if self.attack {
+            self.step
+        } else {
+            31 - self.step
+        }
+    }
+}
+
+struct Sunsoft5B {
+    select: u8,
+    periods: [u16; 3],
+    counters: [u32; 3],
+    tones: [bool; 3],
+    noise_period: u8,
+    noise_counter: u32,
+    lfsr: u32,
+    mixer: u8, // Bits 0-2 disable the tones, bits 3-5 the noise
+    volumes: [u8; 3],
+    envelope: Envelope,
+}
+
+impl Sunsoft5B {
+    fn new() -> Sunsoft5B {
+        Sunsoft5B {
+            select: 0,
+            periods: [0; 3],
+            counters: [0; 3],
+            tones: [false; 3],
+            noise_period: 0,
+            noise_counter: 0,
+            lfsr: 1,
+            mixer: 0xFF,
+            volumes: [0; 3],
+            envelope: Envelope::new(),
+        }
+    }
+
+    fn write(&mut self, v: u8) {
+        match self.select {
+            r @ 0x00..=0x05 => {
+                let p = &mut self.periods[usize::from(r / 2)];
+                *p = if r & 0x01 == 0 {
+                    (*p & 0x0F00) | u16::from(v)
+                } else {
+                    (*p & 0x00FF) | u16::from(v & 0x0F) << 8
+                };
+            }
+            0x06 => self.noise_period = v & 0x1F,
+            0x07 => self.mixer = v,
+            r @ 0x08..=0x0A => self.volumes[usize::from(r - 0x08)] = v & 0x1F,
+            0x0B => self.envelope.period = (self.envelope.period & 0xFF00) | u16::from(v),
+            0x0C => self.envelope.period = (self.envelope.period & 0x00FF) | u16::from(v) << 8,
+            0x0D => self.envelope.set_shape(v),
+            _ => (),
+        }
+    }
+
+    // Tones flip every 16 CPU cycles times their period, the noise steps half as often
+    fn cycle(&mut self) {
+        for i in 0..3 {
+            if self.counters[i] > 0 {
+                self.counters[i] -= 1;
+            } else {
+                self.counters[i] = u32::from(self.periods[i].max(1)) * 16 - 1;
+                self.tones[i] = !self.tones[i];
+            }
+        }
+
+        if self.noise_counter > 0 {
+            self.noise_counter -= 1;
+        } else {
+            self.noise_counter = u32::from(self.noise_period.max(1)) * 32 - 1;
+            let bit = (self.lfsr ^ (self.lfsr >> 3)) & 0x01;
+            self.lfsr = (self.lfsr >> 1) | (bit << 16);
+        }
+
+        self.envelope.clock();
+    }
+
+    // 1.5 dB per envelope step, a volume step being worth two of them
+    fn level(i: u8) -> f32 {
+        if i == 0 {
+            0.0
+        } else {
+            10f32.powf(f32::from(31 - i) * -1.5 / 20.0)
+        }
+    }
+
+    fn output(&self) -> f32 {
+        (0..3)
+            .filter(|&i| {
+                let tone = self.tones[i] || self.mixer & (0x01 << i) != 0;
+                let noise = self.lfsr & 0x01 != 0 || self.mixer & (0x08 << i) != 0;
+                tone && noise
+            })
+            .map(|i| match self.volumes[i] {
+                v if v & 0x10 != 0 => Sunsoft5B::level(self.envelope.level()),
+                0 => 0.0,
+                v => Sunsoft5B::level(v * 2 + 1),
+            })
+            .sum()
+    }
+}
+// #endregion
+
+// Mapper 69: four 8K PRG banks, the one at $6000 being ROM or RAM, 1K CHR banks and a
+// 16-bit IRQ counter decremented every CPU cycle
+pub struct FME7 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+
+    command: u8,
+    chr_banks: [u8; 8],
+    prg_banks: [u8; 4], // $6000 with its RAM bits, then $8000-$DFFF
+    mirroring: u8,
+
+    irq_enabled: bool,
+    counter_enabled: bool,
+    counter: u16,
+    irq: bool,
+
+    audio: Sunsoft5B,
+}
+
+impl FME7 {
+    pub fn new(d: &[u8]) -> Result<FME7, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(FME7::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> FME7 {
+        let chr_ram = h.chr_rom_size == 0;
+
+        FME7 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            command: 0,
+            chr_banks: [0; 8],
+            prg_banks: [0; 4],
+            mirroring: 0,
+            irq_enabled: false,
+            counter_enabled: false,
+            counter: 0,
+            irq: false,
+            audio: Sunsoft5B::new(),
+        }
+    }
+
+    // #region Banking
+    fn prg_bank(&self, a: u16) -> usize {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match a {
+            0xE000..=0xFFFF => banks - 1,
+            _ => usize::from(self.prg_banks[usize::from((a - 0x6000) >> 13)] & 0x3F),
+        };
+
+        bank % banks
+    }
+
+    // Where $6000-$7FFF reads from, if anywhere
+    fn ram_addr(&self, a: u16) -> Option<usize> {
+        let v = self.prg_banks[0];
+
+        if v & 0x40 == 0 || v & 0x80 == 0 || self.prg_ram.is_empty() {
+            None
+        } else {
+            let i = usize::from(v & 0x3F) * PRG_BANK_SIZE + (a as usize & 0x1FFF);
+            Some(i % self.prg_ram.len())
+        }
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        let bank = usize::from(self.chr_banks[usize::from(a >> 10)]);
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((bank % banks) * CHR_BANK_SIZE + (a as usize & 0x03FF)) % self.chr.len()
+    }
+    // #endregion
+
+    // $A000-$BFFF, for the register $8000-$9FFF selected
+    fn parameter(&mut self, v: u8) {
+        match self.command {
+            c @ 0x00..=0x07 => self.chr_banks[usize::from(c)] = v,
+            c @ 0x08..=0x0B => self.prg_banks[usize::from(c - 0x08)] = v,
+            0x0C => self.mirroring = v & 0x03,
+            0x0D => {
+                self.irq_enabled = v & 0x01 != 0;
+                self.counter_enabled = v & 0x80 != 0;
+                self.irq = false;
+            }
+            0x0E => self.counter = (self.counter & 0xFF00) | u16::from(v),
+            _ => self.counter = (self.counter & 0x00FF) | u16::from(v) << 8,
+        }
+    }
+}
+
+impl Mapper for FME7 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.prg_banks[0] & 0x40 != 0 => {
+                self.ram_addr(a).map_or(0, |i| self.prg_ram[i])
+            }
+            0x6000..=0xFFFF => {
+                let i = self.prg_bank(a) * PRG_BANK_SIZE + (a as usize & 0x1FFF);
+                self.prg_rom[i % self.prg_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.prg_banks[0] & 0x40 == 0 => 0xFF,
+            0x6000..=0x7FFF if self.ram_addr(a).is_some() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(i) = self.ram_addr(a) {
+                    self.prg_ram[i] = v
+                }
+            }
+            0x8000..=0x9FFF => self.command = v & 0x0F,
+            0xA000..=0xBFFF => self.parameter(v),
+            0xC000..=0xDFFF => self.audio.select = v,
+            0xE000..=0xFFFF => self.audio.write(v),
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        if self.counter_enabled {
+            self.counter = self.counter.wrapping_sub(1);
+            if self.counter == 0xFFFF && self.irq_enabled {
+                self.irq = true;
+            }
+        }
+
+        self.audio.cycle();
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.mirroring {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    fn audio(&self) -> f32 {
+        self.audio.output() * VOLUME
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
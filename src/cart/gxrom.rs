@@ -0,0 +1,115 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x8000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+// Mapper 66: one register selecting a 32K PRG bank with bits 4-5 and an 8K CHR bank
+// with bits 0-1. The boards have bus conflicts.
+pub struct GxROM {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
+    bus_conflicts: bool,
+    bank: u8,
+}
+
+impl GxROM {
+    pub fn new(d: &[u8]) -> Result<GxROM, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(GxROM::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> GxROM {
+        GxROM {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram: h.chr_rom_size == 0,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::trainer(h, d),
+            mirroring: h.mirroring,
+            bus_conflicts: true,
+            bank: 0,
+        }
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((usize::from(self.bank & 0x03) % banks) * CHR_BANK_SIZE + a as usize) % self.chr.len()
+    }
+}
+
+impl Mapper for GxROM {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = usize::from((self.bank >> 4) & 0x03) % banks;
+
+        match a {
+            0x6000..=0x7FFF => self.prg_ram.get(a as usize - 0x6000).cloned().unwrap_or(0),
+            0x8000..=0xFFFF => {
+                self.prg_rom[(bank * PRG_BANK_SIZE + (a as usize & 0x7FFF)) % self.prg_rom.len()]
+            }
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(b) = self.prg_ram.get_mut(a as usize - 0x6000) {
+                    *b = v
+                }
+            }
+            0x8000..=0xFFFF => self.bank = self.conflict(a, v),
+            _ => (),
+        }
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+
+    fn set_bus_conflicts(&mut self, on: bool) {
+        self.bus_conflicts = on;
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
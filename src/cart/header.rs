@@ -0,0 +1,282 @@
+use std::error::Error;
+use std::fmt;
+
+use cart::Mirroring;
+
+pub const HEADER_SIZE: usize = 16;
+pub const TRAINER_SIZE: usize = 512;
+pub const PRG_ROM_PAGE_SIZE: usize = 16384;
+pub const CHR_ROM_PAGE_SIZE: usize = 8192;
+pub const PRG_RAM_PAGE_SIZE: usize = 8192;
+
+const MAGIC: &[u8] = b"NES\x1A";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timing {
+    Ntsc,
+    Pal,
+    Multi,
+    Dendy,
+}
+
+// VS System PPU and hardware types and the extended console type are kept as the raw
+// NES 2.0 nibbles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Console {
+    Nes,
+    VsSystem { ppu: u8, hardware: u8 },
+    Playchoice,
+    Extended(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderError {
+    Truncated { expected: usize, found: usize },
+    BadMagic,
+    NoPrgRom,
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::HeaderError::*;
+        match *self {
+            Truncated { expected, found } => write!(
+                f,
+                "ROM is truncated: header declares {} bytes but the file has {}",
+                expected, found
+            ),
+            BadMagic => write!(f, "Not an iNES file, the NES<EOF> magic is missing"),
+            NoPrgRom => write!(f, "Header declares no PRG ROM"),
+        }
+    }
+}
+
+impl Error for HeaderError {}
+
+// iNES 1.0 headers fill the NES 2.0 only fields with what they imply: no submapper,
+// no NVRAM, 8K of CHR RAM when there's no CHR ROM and no expansion device
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RomHeader {
+    pub nes2: bool,
+    pub prg_rom_size: usize,
+    pub chr_rom_size: usize,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub battery: bool,
+    pub trainer: bool,
+    pub console: Console,
+    pub timing: Timing,
+    pub misc_roms: u8,
+    pub expansion: u8,
+}
+
+impl RomHeader {
+    pub fn parse(d: &[u8]) -> Result<RomHeader, HeaderError> {
+        if d.len() < HEADER_SIZE {
+            return Err(HeaderError::Truncated {
+                expected: HEADER_SIZE,
+                found: d.len(),
+            });
+        }
+
+        if &d[..4] != MAGIC {
+            return Err(HeaderError::BadMagic);
+        }
+
+        let mut header = if d[7] & 0x0C == 0x08 {
+            RomHeader::nes2(d)
+        } else {
+            RomHeader::ines(d)
+        };
+
+        header.mirroring = match (d[6] & 0x08 != 0, d[6] & 0x01 != 0) {
+            (true, _) => Mirroring::FourScreen,
+            (false, true) => Mirroring::Vertical,
+            (false, false) => Mirroring::Horizontal,
+        };
+        header.battery = d[6] & 0x02 != 0;
+        header.trainer = d[6] & 0x04 != 0;
+
+        if header.prg_rom_size == 0 {
+            return Err(HeaderError::NoPrgRom);
+        }
+
+        if d.len() < header.file_size() {
+            return Err(HeaderError::Truncated {
+                expected: header.file_size(),
+                found: d.len(),
+            });
+        }
+
+        Ok(header)
+    }
+
+    fn ines(d: &[u8]) -> RomHeader {
+        // Old dumping tools wrote their name over bytes 7-15, e.g. "DiskDude!". Those
+        // bytes are garbage whenever the zero padding at 12-15 isn't there.
+        let flags = if d[12..16].iter().any(|&b| b != 0) {
+            [0, 0, 0]
+        } else {
+            [d[7], d[8], d[9]]
+        };
+
+        let chr_rom_size = usize::from(d[5]) * CHR_ROM_PAGE_SIZE;
+
+        RomHeader {
+            nes2: false,
+            prg_rom_size: usize::from(d[4]) * PRG_ROM_PAGE_SIZE,
+            chr_rom_size,
+            prg_ram_size: usize::from(flags[1].max(1)) * PRG_RAM_PAGE_SIZE,
+            prg_nvram_size: 0,
+            chr_ram_size: if chr_rom_size == 0 {
+                CHR_ROM_PAGE_SIZE
+            } else {
+                0
+            },
+            chr_nvram_size: 0,
+            mapper: u16::from(flags[0] & 0xF0) | u16::from(d[6] >> 4),
+            submapper: 0,
+            mirroring: Mirroring::Horizontal,
+            battery: false,
+            trainer: false,
+            console: match flags[0] & 0x03 {
+                0 => Console::Nes,
+                1 => Console::VsSystem {
+                    ppu: 0,
+                    hardware: 0,
+                },
+                _ => Console::Playchoice,
+            },
+            timing: if flags[2] & 0x01 != 0 {
+                Timing::Pal
+            } else {
+                Timing::Ntsc
+            },
+            misc_roms: 0,
+            expansion: 0,
+        }
+    }
+
+    fn nes2(d: &[u8]) -> RomHeader {
+        // RAM sizes are 64 << n bytes, with 0 meaning none
+        let ram = |n: u8| if n == 0 { 0 } else { 64 << n };
+
+        RomHeader {
+            nes2: true,
+            prg_rom_size: rom_size(d[4], d[9] & 0x0F, PRG_ROM_PAGE_SIZE),
+            chr_rom_size: rom_size(d[5], d[9] >> 4, CHR_ROM_PAGE_SIZE),
+            prg_ram_size: ram(d[10] & 0x0F),
+            prg_nvram_size: ram(d[10] >> 4),
+            chr_ram_size: ram(d[11] & 0x0F),
+            chr_nvram_size: ram(d[11] >> 4),
+            mapper: (u16::from(d[8] & 0x0F) << 8) | u16::from(d[7] & 0xF0) | u16::from(d[6] >> 4),
+            submapper: d[8] >> 4,
+            mirroring: Mirroring::Horizontal,
+            battery: false,
+            trainer: false,
+            console: match d[7] & 0x03 {
+                0 => Console::Nes,
+                1 => Console::VsSystem {
+                    ppu: d[13] & 0x0F,
+                    hardware: d[13] >> 4,
+                },
+                2 => Console::Playchoice,
+                _ => Console::Extended(d[13] & 0x0F),
+            },
+            timing: match d[12] & 0x03 {
+                0 => Timing::Ntsc,
+                1 => Timing::Pal,
+                2 => Timing::Multi,
+                _ => Timing::Dendy,
+            },
+            misc_roms: d[14] & 0x03,
+            expansion: d[15] & 0x3F,
+        }
+    }
+
+    // Size of the whole dump this header describes, PlayChoice data excluded
+    pub fn file_size(&self) -> usize {
+        self.chr_offset().saturating_add(self.chr_rom_size)
+    }
+
+    pub fn prg_offset(&self) -> usize {
+        HEADER_SIZE + if self.trainer { TRAINER_SIZE } else { 0 }
+    }
+
+    pub fn chr_offset(&self) -> usize {
+        self.prg_offset().saturating_add(self.prg_rom_size)
+    }
+
+    pub fn prg_ram_total(&self) -> usize {
+        self.prg_ram_size + self.prg_nvram_size
+    }
+
+    pub fn chr_ram_total(&self) -> usize {
+        self.chr_ram_size + self.chr_nvram_size
+    }
+
+    // For the discrete boards that may or may not have bus conflicts, submapper 2 says
+    // they do. Submapper 1 and unspecified both mean none.
+    pub fn bus_conflicts(&self) -> bool {
+        self.submapper == 2
+    }
+
+    // #region Slices
+    pub fn trainer<'a>(&self, d: &'a [u8]) -> Option<&'a [u8]> {
+        if self.trainer {
+            Some(&d[HEADER_SIZE..HEADER_SIZE + TRAINER_SIZE])
+        } else {
+            None
+        }
+    }
+
+    pub fn prg_rom<'a>(&self, d: &'a [u8]) -> &'a [u8] {
+        &d[self.prg_offset()..self.chr_offset()]
+    }
+
+    pub fn chr_rom<'a>(&self, d: &'a [u8]) -> &'a [u8] {
+        &d[self.chr_offset()..self.file_size()]
+    }
+    // #endregion
+
+    // Zeroed PRG RAM, with the trainer at $7000 when there is one. At least 8K for those.
+    pub fn prg_ram(&self, d: &[u8]) -> Vec<u8> {
+        match self.trainer(d) {
+            Some(t) => {
+                let mut r = vec![0; self.prg_ram_total().max(0x2000)];
+                r[0x1000..0x1000 + TRAINER_SIZE].copy_from_slice(t);
+                r
+            }
+            None => vec![0; self.prg_ram_total()],
+        }
+    }
+
+    // The pattern table memory of the board: its CHR ROM, or zeroed CHR RAM when it has
+    // none. At least 8K, since headers often leave the RAM size out.
+    pub fn chr(&self, d: &[u8]) -> Vec<u8> {
+        if self.chr_rom_size == 0 {
+            vec![0; self.chr_ram_total().max(0x2000)]
+        } else {
+            self.chr_rom(d).to_vec()
+        }
+    }
+}
+
+// NES 2.0 ROM size from its LSB and MSB nibble. An MSB of $F switches the LSB to an
+// EEEEEEMM exponent-multiplier form, giving 2^E * (MM * 2 + 1) bytes.
+fn rom_size(lsb: u8, msb: u8, page: usize) -> usize {
+    if msb == 0x0F {
+        let multiplier = usize::from(lsb & 0x03) * 2 + 1;
+        1usize
+            .checked_shl(u32::from(lsb >> 2))
+            .unwrap_or(usize::MAX)
+            .saturating_mul(multiplier)
+    } else {
+        ((usize::from(msb) << 8) | usize::from(lsb)) * page
+    }
+}
@@ -0,0 +1,236 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+const RAM_BANK_SIZE: usize = 0x2000;
+
+// Boards that reuse the CHR bank lines to drive extra PRG ROM or PRG RAM address bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Board {
+    Standard,
+    Snrom, // CHR bit 4 disables PRG RAM
+    Sorom, // CHR bit 3 selects one of two 8K PRG RAM banks
+    Surom, // CHR bit 4 selects a 256K PRG ROM half
+    Sxrom, // Both SUROM's PRG half and CHR bits 2-3 selecting an 8K PRG RAM bank
+}
+
+impl Board {
+    // Submappers 1, 2 and 4 name the board, otherwise it's guessed from the memory sizes.
+    // SNROM has no submapper of its own.
+    fn detect(h: &RomHeader) -> Board {
+        match h.submapper {
+            1 => Board::Surom,
+            2 => Board::Sorom,
+            4 => Board::Sxrom,
+            _ if h.prg_rom_size > 0x40000 && h.prg_ram_total() > 0x4000 => Board::Sxrom,
+            _ if h.prg_rom_size > 0x40000 => Board::Surom,
+            _ if h.prg_ram_total() > 0x2000 => Board::Sorom,
+            _ if h.chr_rom_size == 0 && h.prg_ram_total() > 0 => Board::Snrom,
+            _ => Board::Standard,
+        }
+    }
+}
+
+pub struct MMC1 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    board: Board,
+
+    shift: u8, // Bits written so far, with a marker bit above them
+    control: u8,
+    chr_bank: [u8; 2],
+    prg_bank: u8,
+    cooldown: u8, // Cycles left during which writes to the serial port are ignored
+}
+
+impl MMC1 {
+    pub fn new(d: &[u8]) -> Result<MMC1, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(MMC1::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> MMC1 {
+        let chr_ram = h.chr_rom_size == 0;
+
+        MMC1 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            board: Board::detect(h),
+            shift: 0x10,
+            control: 0x0C,
+            chr_bank: [0; 2],
+            prg_bank: 0,
+            cooldown: 0,
+        }
+    }
+
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    // #region Serial Port
+    fn serial(&mut self, a: u16, v: u8) {
+        // The second write of a read-modify-write instruction lands on the next cycle
+        // and doesn't reach the shift register
+        let ignored = self.cooldown > 0;
+        self.cooldown = 2;
+
+        if ignored {
+            return;
+        }
+
+        if v & 0x80 != 0 {
+            self.shift = 0x10;
+            self.control |= 0x0C;
+            return;
+        }
+
+        let done = self.shift & 0x01 != 0;
+        self.shift = (self.shift >> 1) | ((v & 0x01) << 4);
+
+        if done {
+            let value = self.shift;
+            self.shift = 0x10;
+
+            match a {
+                0x8000..=0x9FFF => self.control = value,
+                0xA000..=0xBFFF => self.chr_bank[0] = value,
+                0xC000..=0xDFFF => self.chr_bank[1] = value,
+                _ => self.prg_bank = value,
+            }
+        }
+    }
+    // #endregion
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> usize {
+        let outer = match self.board {
+            Board::Surom | Board::Sxrom => self.chr_bank[0] & 0x10,
+            _ => 0,
+        };
+        let bank = self.prg_bank & 0x0F;
+
+        let bank = match ((self.control >> 2) & 0x03, a) {
+            (0, _) | (1, _) => (bank & 0x0E) | ((a >> 14) & 0x01) as u8,
+            (2, 0x8000..=0xBFFF) => 0,
+            (2, _) => bank,
+            (_, 0x8000..=0xBFFF) => bank,
+            (_, _) => 0x0F,
+        };
+
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let i = (usize::from(outer | bank) % banks) * PRG_BANK_SIZE + (a as usize & 0x3FFF);
+        i % self.prg_rom.len()
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        let bank = if self.control & 0x10 == 0 {
+            (self.chr_bank[0] & 0x1E) | ((a >> 12) & 0x01) as u8
+        } else {
+            self.chr_bank[(a >> 12) as usize & 0x01]
+        };
+
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((usize::from(bank) % banks) * CHR_BANK_SIZE + (a as usize & 0x0FFF)) % self.chr.len()
+    }
+
+    fn ram_addr(&self, a: u16) -> Option<usize> {
+        let disabled = self.prg_bank & 0x10 != 0
+            || (self.board == Board::Snrom && self.chr_bank[0] & 0x10 != 0);
+
+        let bank = match self.board {
+            Board::Sorom => (self.chr_bank[0] >> 3) & 0x01,
+            Board::Sxrom => (self.chr_bank[0] >> 2) & 0x03,
+            _ => 0,
+        };
+
+        let banks = self.prg_ram.len() / RAM_BANK_SIZE;
+        if disabled || banks == 0 {
+            None
+        } else {
+            Some((usize::from(bank) % banks) * RAM_BANK_SIZE + (a as usize & 0x1FFF))
+        }
+    }
+    // #endregion
+}
+
+impl Mapper for MMC1 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF => self.ram_addr(a).map_or(0, |i| self.prg_ram[i]),
+            0x8000..=0xFFFF => self.prg_rom[self.prg_addr(a)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.ram_addr(a).is_some() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(i) = self.ram_addr(a) {
+                    self.prg_ram[i] = v
+                }
+            }
+            0x8000..=0xFFFF => self.serial(a, v),
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.cooldown = self.cooldown.saturating_sub(1);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
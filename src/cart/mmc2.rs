@@ -0,0 +1,256 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const CHR_BANK_SIZE: usize = 0x1000;
+
+// #region Latches
+// Each 4K pattern table has an $FD and an $FE bank. Fetching tile $FD or $FE flips the
+// latch of that table once the fetch is done, so the next one sees the other bank.
+struct Latches {
+    banks: [[u8; 2]; 2],
+    latch: [usize; 2],
+    exact: bool, // MMC2 only reacts to $0FD8 and $0FE8 on the left table
+}
+
+impl Latches {
+    fn new(exact: bool) -> Latches {
+        Latches {
+            banks: [[0; 2]; 2],
+            latch: [1; 2],
+            exact,
+        }
+    }
+
+    fn addr(&self, a: u16, len: usize) -> usize {
+        let table = usize::from(a >> 12) & 0x01;
+        let bank = self.banks[table][self.latch[table]];
+        (usize::from(bank) * CHR_BANK_SIZE + (a as usize & 0x0FFF)) % len
+    }
+
+    fn update(&mut self, a: u16) {
+        match a {
+            0x0FD8 => self.latch[0] = 0,
+            0x0FE8 => self.latch[0] = 1,
+            0x0FD9..=0x0FDF if !self.exact => self.latch[0] = 0,
+            0x0FE9..=0x0FEF if !self.exact => self.latch[0] = 1,
+            0x1FD8..=0x1FDF => self.latch[1] = 0,
+            0x1FE8..=0x1FEF => self.latch[1] = 1,
+            _ => (),
+        }
+    }
+
+    // $B000-$EFFF, one register per table and latch state
+    fn write(&mut self, a: u16, v: u8) {
+        let i = usize::from((a - 0xB000) >> 12);
+        self.banks[i / 2][i % 2] = v & 0x1F;
+    }
+}
+// #endregion
+
+fn mirroring(v: u8) -> Mirroring {
+    if v & 0x01 == 0 {
+        Mirroring::Vertical
+    } else {
+        Mirroring::Horizontal
+    }
+}
+
+// #region MMC2
+// Mapper 9: switchable 8K PRG bank at $8000 and the last three banks fixed after it
+pub struct MMC2 {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    latches: Latches,
+    prg_bank: u8,
+    mirroring: u8,
+}
+
+impl MMC2 {
+    pub fn new(d: &[u8]) -> Result<MMC2, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(MMC2::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> MMC2 {
+        MMC2 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr_rom: h.chr_rom(d).to_vec(),
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::trainer(h, d),
+            latches: Latches::new(true),
+            prg_bank: 0,
+            mirroring: 0,
+        }
+    }
+}
+
+impl Mapper for MMC2 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        let banks = (self.prg_rom.len() / 0x2000).max(1);
+        let bank = match a {
+            0x6000..=0x7FFF => {
+                return self.prg_ram.get(a as usize - 0x6000).cloned().unwrap_or(0);
+            }
+            0x8000..=0x9FFF => usize::from(self.prg_bank) % banks,
+            0xA000..=0xFFFF => (banks.saturating_sub(4) + usize::from((a - 0x8000) >> 13)) % banks,
+            _ => return 0,
+        };
+
+        self.prg_rom[(bank * 0x2000 + (a as usize & 0x1FFF)) % self.prg_rom.len()]
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.prg_ram.len() > a as usize - 0x6000 => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(b) = self.prg_ram.get_mut(a as usize - 0x6000) {
+                    *b = v
+                }
+            }
+            0xA000..=0xAFFF => self.prg_bank = v & 0x0F,
+            0xB000..=0xEFFF => self.latches.write(a, v),
+            0xF000..=0xFFFF => self.mirroring = v,
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF if !self.chr_rom.is_empty() => {
+                let v = self.chr_rom[self.latches.addr(a, self.chr_rom.len())];
+                self.latches.update(a);
+                v
+            }
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        if let 0x2000..=0x3EFF = a {
+            let m = self.mirroring();
+            self.nametables.write(m, a, v)
+        }
+    }
+
+    fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        mirroring(self.mirroring)
+    }
+}
+// #endregion
+
+// #region MMC4
+// Mapper 10: switchable 16K PRG bank at $8000, the last one at $C000 and 8K of PRG RAM
+pub struct MMC4 {
+    prg_rom: Vec<u8>,
+    chr_rom: Vec<u8>,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    latches: Latches,
+    prg_bank: u8,
+    mirroring: u8,
+}
+
+impl MMC4 {
+    pub fn new(d: &[u8]) -> Result<MMC4, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(MMC4::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> MMC4 {
+        MMC4 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr_rom: h.chr_rom(d).to_vec(),
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            latches: Latches::new(false),
+            prg_bank: 0,
+            mirroring: 0,
+        }
+    }
+}
+
+impl Mapper for MMC4 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        let banks = (self.prg_rom.len() / 0x4000).max(1);
+        let bank = match a {
+            0x6000..=0x7FFF => {
+                return self.prg_ram.get(a as usize - 0x6000).cloned().unwrap_or(0);
+            }
+            0x8000..=0xBFFF => usize::from(self.prg_bank) % banks,
+            0xC000..=0xFFFF => banks - 1,
+            _ => return 0,
+        };
+
+        self.prg_rom[(bank * 0x4000 + (a as usize & 0x3FFF)) % self.prg_rom.len()]
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.prg_ram.len() > a as usize - 0x6000 => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(b) = self.prg_ram.get_mut(a as usize - 0x6000) {
+                    *b = v
+                }
+            }
+            0xA000..=0xAFFF => self.prg_bank = v & 0x0F,
+            0xB000..=0xEFFF => self.latches.write(a, v),
+            0xF000..=0xFFFF => self.mirroring = v,
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF if !self.chr_rom.is_empty() => {
+                let v = self.chr_rom[self.latches.addr(a, self.chr_rom.len())];
+                self.latches.update(a);
+                v
+            }
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        if let 0x2000..=0x3EFF = a {
+            let m = self.mirroring();
+            self.nametables.write(m, a, v)
+        }
+    }
+
+    fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        mirroring(self.mirroring)
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
+// #endregion
@@ -0,0 +1,260 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// CPU cycles A12 has to stay low before a rising edge clocks the IRQ counter, which
+// filters out the toggling during sprite fetches
+const A12_FILTER: u64 = 3;
+
+// Rev A only raises an IRQ when the counter reaches 0 by decrementing it or by a
+// reload the program asked for. Rev B and later do it on every clock that leaves 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Revision {
+    A,
+    B,
+}
+
+pub struct MMC3 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    four_screen: bool,
+    revision: Revision,
+
+    select: u8,
+    banks: [u8; 8],
+    mirroring: u8,
+    ram_protect: u8,
+
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq: bool,
+
+    cycles: u64,
+    a12: bool,
+    a12_low_since: u64,
+}
+
+impl MMC3 {
+    pub fn new(d: &[u8]) -> Result<MMC3, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(MMC3::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from. Submapper 4 selects the Rev A IRQ.
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> MMC3 {
+        let chr_ram = h.chr_rom_size == 0;
+
+        MMC3 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            four_screen: h.mirroring == Mirroring::FourScreen,
+            revision: if h.submapper == 4 {
+                Revision::A
+            } else {
+                Revision::B
+            },
+            select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring: 0,
+            ram_protect: 0x80,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+            cycles: 0,
+            a12: false,
+            a12_low_since: 0,
+        }
+    }
+
+    pub fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    pub fn set_revision(&mut self, r: Revision) {
+        self.revision = r;
+    }
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> usize {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let swap = self.select & 0x40 != 0;
+
+        let bank = match (a >> 13) & 0x03 {
+            0 if swap => banks.saturating_sub(2),
+            0 => usize::from(self.banks[6]),
+            1 => usize::from(self.banks[7]),
+            2 if swap => usize::from(self.banks[6]),
+            2 => banks.saturating_sub(2),
+            _ => banks - 1,
+        };
+
+        ((bank % banks) * PRG_BANK_SIZE + (a as usize & 0x1FFF)) % self.prg_rom.len()
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        // A12 inversion swaps the 2K and 1K halves
+        let a = if self.select & 0x80 != 0 {
+            a ^ 0x1000
+        } else {
+            a
+        };
+
+        let bank = match a >> 10 {
+            0 => self.banks[0] & 0xFE,
+            1 => self.banks[0] | 0x01,
+            2 => self.banks[1] & 0xFE,
+            3 => self.banks[1] | 0x01,
+            n => self.banks[n as usize - 2],
+        };
+
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((usize::from(bank) % banks) * CHR_BANK_SIZE + (a as usize & 0x03FF)) % self.chr.len()
+    }
+
+    fn ram_addr(&self, a: u16) -> Option<usize> {
+        if self.ram_protect & 0x80 == 0 || self.prg_ram.is_empty() {
+            None
+        } else {
+            Some((a as usize - 0x6000) % self.prg_ram.len())
+        }
+    }
+    // #endregion
+
+    // #region IRQ
+    fn clock_irq(&mut self) {
+        let before = self.irq_counter;
+        let reload = self.irq_reload;
+
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        let fire = match self.revision {
+            Revision::A => self.irq_counter == 0 && (before != 0 || reload),
+            Revision::B => self.irq_counter == 0,
+        };
+
+        if fire && self.irq_enabled {
+            self.irq = true;
+        }
+    }
+    // #endregion
+}
+
+impl Mapper for MMC3 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF => self.ram_addr(a).map_or(0, |i| self.prg_ram[i]),
+            0x8000..=0xFFFF => self.prg_rom[self.prg_addr(a)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.ram_addr(a).is_some() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match (a, a & 0x01) {
+            (0x6000..=0x7FFF, _) => {
+                if let Some(i) = self.ram_addr(a) {
+                    if self.ram_protect & 0x40 == 0 {
+                        self.prg_ram[i] = v
+                    }
+                }
+            }
+            (0x8000..=0x9FFF, 0) => self.select = v,
+            (0x8000..=0x9FFF, _) => self.banks[usize::from(self.select & 0x07)] = v,
+            (0xA000..=0xBFFF, 0) => self.mirroring = v & 0x01,
+            (0xA000..=0xBFFF, _) => self.ram_protect = v,
+            (0xC000..=0xDFFF, 0) => self.irq_latch = v,
+            (0xC000..=0xDFFF, _) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (0xE000..=0xFFFF, 0) => {
+                self.irq_enabled = false;
+                self.irq = false;
+            }
+            (0xE000..=0xFFFF, _) => self.irq_enabled = true,
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn ppu_bus(&mut self, a: u16) {
+        let a12 = a & 0x1000 != 0;
+
+        if a12 && !self.a12 && self.cycles - self.a12_low_since >= A12_FILTER {
+            self.clock_irq();
+        } else if !a12 && self.a12 {
+            self.a12_low_since = self.cycles;
+        }
+
+        self.a12 = a12;
+    }
+
+    fn cycle(&mut self) {
+        self.cycles += 1;
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match (self.four_screen, self.mirroring) {
+            (true, _) => Mirroring::FourScreen,
+            (false, 0) => Mirroring::Vertical,
+            (false, _) => Mirroring::Horizontal,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
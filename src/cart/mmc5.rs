@@ -0,0 +1,438 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+
+// PPU fetches per rendered scanline: 32 background tiles, 8 sprites and the first two
+// tiles of the next line, four fetches each
+const SPRITE_FETCHES: usize = 128;
+const PREFETCHES: usize = 160;
+
+// CPU cycles without a PPU fetch after which rendering is considered over
+const IDLE_CYCLES: u8 = 3;
+
+enum Prg {
+    Rom(usize),
+    Ram(usize),
+}
+
+// What the PPU is fetching, guessed from the position of the fetch in the scanline
+#[derive(Clone, Copy)]
+enum Fetch {
+    Background { tile: usize, line: usize },
+    Sprite,
+    Other,
+}
+
+pub struct MMC5 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    prg_ram: PrgRam,
+    exram: [u8; 0x400],
+    ciram: [u8; 0x800],
+
+    prg_mode: u8,
+    chr_mode: u8,
+    ram_protect: [u8; 2],
+    exram_mode: u8,
+    nametables: u8,
+    fill_tile: u8,
+    fill_attr: u8,
+    prg_banks: [u8; 5],   // $5113-$5117
+    chr_banks: [u16; 12], // $5120-$512B with the upper bits from $5130
+    chr_upper: u8,
+    chr_set: usize, // Set of the last written CHR register, 0 for $5120-$5127
+
+    split: u8,
+    split_scroll: u8,
+    split_bank: u8,
+
+    irq_compare: u8,
+    irq_enabled: bool,
+    irq_pending: bool,
+
+    multiplicand: u8,
+    multiplier: u8,
+
+    // The PPU as the mapper sees it
+    tall_sprites: bool,
+    in_frame: bool,
+    scanline: u8,
+    last_fetch: u16,
+    repeats: u8,
+    fetch: usize,
+    idle: u8,
+    ex_attr: u8, // ExRAM byte of the background tile being fetched
+}
+
+impl MMC5 {
+    pub fn new(d: &[u8]) -> Result<MMC5, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(MMC5::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> MMC5 {
+        let chr_ram = h.chr_rom_size == 0;
+
+        MMC5 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            prg_ram: PrgRam::new(h, d),
+            exram: [0; 0x400],
+            ciram: [0; 0x800],
+            prg_mode: 3,
+            chr_mode: 0,
+            ram_protect: [0; 2],
+            exram_mode: 0,
+            nametables: 0,
+            fill_tile: 0,
+            fill_attr: 0,
+            prg_banks: [0, 0, 0, 0, 0xFF],
+            chr_banks: [0; 12],
+            chr_upper: 0,
+            chr_set: 0,
+            split: 0,
+            split_scroll: 0,
+            split_bank: 0,
+            irq_compare: 0,
+            irq_enabled: false,
+            irq_pending: false,
+            multiplicand: 0xFF,
+            multiplier: 0xFF,
+            tall_sprites: false,
+            in_frame: false,
+            scanline: 0,
+            last_fetch: 0,
+            repeats: 0,
+            fetch: 0,
+            idle: 0,
+            ex_attr: 0,
+        }
+    }
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> Prg {
+        let b = &self.prg_banks;
+
+        let (v, size) = match (self.prg_mode, a) {
+            (_, 0x6000..=0x7FFF) => (b[0] & 0x7F, 0x2000),
+            (0, _) => (b[4] | 0x80, 0x8000),
+            (1, 0x8000..=0xBFFF) | (2, 0x8000..=0xBFFF) => (b[2], 0x4000),
+            (1, _) => (b[4] | 0x80, 0x4000),
+            (2, 0xC000..=0xDFFF) => (b[3], 0x2000),
+            (_, 0xE000..=0xFFFF) => (b[4] | 0x80, 0x2000),
+            (_, _) => (b[usize::from((a - 0x6000) >> 13)], 0x2000),
+        };
+
+        // Bit 7 selects ROM, the bank number counts 8K pages whatever the window size
+        let i = ((usize::from(v & 0x7F) * PRG_BANK_SIZE) & !(size - 1)) + (a as usize & (size - 1));
+        if v & 0x80 != 0 {
+            Prg::Rom(i % self.prg_rom.len())
+        } else {
+            Prg::Ram(i % (8 * PRG_BANK_SIZE))
+        }
+    }
+
+    fn chr_addr(&self, a: u16, set: usize) -> usize {
+        let a = a as usize;
+
+        let (size, r) = match (self.chr_mode, set) {
+            (0, 0) => (0x2000, 7),
+            (1, 0) => (0x1000, 3 + (a >> 12) * 4),
+            (2, 0) => (0x0800, 1 + (a >> 11) * 2),
+            (_, 0) => (0x0400, a >> 10),
+            (0, _) => (0x2000, 11),
+            (1, _) => (0x1000, 11),
+            (2, _) => (0x0800, 9 + ((a >> 11) & 0x01) * 2),
+            (_, _) => (0x0400, 8 + ((a >> 10) & 0x03)),
+        };
+
+        (usize::from(self.chr_banks[r]) * size + a % size) % self.chr.len()
+    }
+
+    fn ram_writable(&self) -> bool {
+        self.ram_protect == [0x02, 0x01]
+    }
+    // #endregion
+
+    // #region Scanline Detection
+    // The PPU reads the same nametable byte three times in a row around the start of
+    // each rendered line. Returns the position of the fetch in the current line.
+    fn track(&mut self, a: u16) -> usize {
+        self.idle = 0;
+
+        if let (0x2000..=0x2FFF, true) = (a, a == self.last_fetch) {
+            self.repeats += 1;
+            if self.repeats == 2 {
+                self.new_line();
+            }
+        } else {
+            self.repeats = 0;
+        }
+
+        self.last_fetch = a;
+        self.fetch += 1;
+        self.fetch - 1
+    }
+
+    fn new_line(&mut self) {
+        self.fetch = 0;
+
+        if self.in_frame {
+            self.scanline = self.scanline.wrapping_add(1);
+            if self.scanline == self.irq_compare {
+                self.irq_pending = true;
+            }
+        } else {
+            self.in_frame = true;
+            self.scanline = 0;
+            self.irq_pending = false;
+        }
+    }
+
+    fn classify(&self, fetch: usize) -> Fetch {
+        let line = usize::from(self.scanline);
+
+        match fetch {
+            _ if !self.in_frame => Fetch::Other,
+            0..=127 => Fetch::Background {
+                tile: (fetch / 4 + 2) & 0x1F,
+                line,
+            },
+            SPRITE_FETCHES..=159 => Fetch::Sprite,
+            PREFETCHES..=167 => Fetch::Background {
+                tile: (fetch - PREFETCHES) / 4,
+                line: line + 1,
+            },
+            _ => Fetch::Other,
+        }
+    }
+    // #endregion
+
+    // #region Nametables
+    fn in_split(&self, tile: usize) -> bool {
+        let count = usize::from(self.split & 0x1F);
+
+        self.split & 0x80 != 0 && self.exram_mode <= 1 && (tile < count) != (self.split & 0x40 != 0)
+    }
+
+    fn split_y(&self, line: usize) -> usize {
+        (usize::from(self.split_scroll) + line) % 240
+    }
+
+    // What $5105 maps the nametable of `a` to: CIRAM page 0 or 1, ExRAM or fill mode
+    fn source(&self, a: u16) -> u8 {
+        (self.nametables >> (((a >> 10) & 0x03) * 2)) & 0x03
+    }
+
+    fn nametable(&self, a: u16) -> u8 {
+        let i = a as usize & 0x03FF;
+
+        match self.source(a) {
+            0 => self.ciram[i],
+            1 => self.ciram[0x400 + i],
+            2 if self.exram_mode <= 1 => self.exram[i],
+            2 => 0,
+            _ if i < 0x3C0 => self.fill_tile,
+            _ => self.fill_attr * 0x55,
+        }
+    }
+
+    fn nametable_fetch(&mut self, a: u16, fetch: usize) -> u8 {
+        let attribute = fetch % 4 == 1;
+
+        match self.classify(fetch) {
+            Fetch::Background { tile, line } if self.in_split(tile) => {
+                let row = self.split_y(line) / 8;
+                if attribute {
+                    let v = self.exram[0x3C0 + row / 4 * 8 + tile / 4];
+                    let shift = (row & 0x02) << 1 | (tile & 0x02);
+                    ((v >> shift) & 0x03) * 0x55
+                } else {
+                    self.exram[(row * 32 + tile) & 0x3FF]
+                }
+            }
+            Fetch::Background { .. } if self.exram_mode == 1 => {
+                if attribute {
+                    (self.ex_attr >> 6) * 0x55
+                } else {
+                    self.ex_attr = self.exram[a as usize & 0x03FF];
+                    self.nametable(a)
+                }
+            }
+            _ => self.nametable(a),
+        }
+    }
+
+    fn pattern_fetch(&self, a: u16, fetch: usize) -> u8 {
+        let i = match self.classify(fetch) {
+            Fetch::Background { tile, line } if self.in_split(tile) => {
+                let fine = (a as usize & 0x0FF8) | (self.split_y(line) & 0x07);
+                usize::from(self.split_bank) * 0x1000 + fine
+            }
+            Fetch::Background { .. } if self.exram_mode == 1 => {
+                let bank = usize::from(self.ex_attr & 0x3F) | usize::from(self.chr_upper) << 6;
+                bank * 0x1000 + (a as usize & 0x0FFF)
+            }
+            Fetch::Background { .. } if self.tall_sprites => return self.chr[self.chr_addr(a, 1)],
+            Fetch::Sprite if self.tall_sprites => return self.chr[self.chr_addr(a, 0)],
+            _ => return self.chr[self.chr_addr(a, self.chr_set)],
+        };
+
+        self.chr[i % self.chr.len()]
+    }
+    // #endregion
+}
+
+impl Mapper for MMC5 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x5204 => u8::from(self.irq_pending) << 7 | u8::from(self.in_frame) << 6,
+            0x5205 => (u16::from(self.multiplicand) * u16::from(self.multiplier)) as u8,
+            0x5206 => ((u16::from(self.multiplicand) * u16::from(self.multiplier)) >> 8) as u8,
+            0x5C00..=0x5FFF if self.exram_mode >= 2 => self.exram[a as usize - 0x5C00],
+            0x6000..=0xFFFF => match self.prg_addr(a) {
+                Prg::Rom(i) => self.prg_rom[i],
+                Prg::Ram(i) if !self.prg_ram.is_empty() => self.prg_ram[i % self.prg_ram.len()],
+                Prg::Ram(_) => 0,
+            },
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x5204 => 0xC0,
+            0x5205..=0x5206 => 0xFF,
+            0x5C00..=0x5FFF if self.exram_mode >= 2 => 0xFF,
+            0x6000..=0xFFFF => match self.prg_addr(a) {
+                Prg::Ram(_) if self.prg_ram.is_empty() => 0x00,
+                _ => 0xFF,
+            },
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_read(&mut self, a: u16) -> u8 {
+        let v = self.cpu_peek(a);
+
+        match a {
+            0x5204 => self.irq_pending = false,
+            // NMI vector fetch
+            0xFFFA | 0xFFFB => self.in_frame = false,
+            _ => (),
+        }
+
+        v
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x5100 => self.prg_mode = v & 0x03,
+            0x5101 => self.chr_mode = v & 0x03,
+            0x5102 | 0x5103 => self.ram_protect[usize::from(a - 0x5102)] = v & 0x03,
+            0x5104 => self.exram_mode = v & 0x03,
+            0x5105 => self.nametables = v,
+            0x5106 => self.fill_tile = v,
+            0x5107 => self.fill_attr = v & 0x03,
+            0x5113..=0x5117 => self.prg_banks[usize::from(a - 0x5113)] = v,
+            0x5120..=0x512B => {
+                self.chr_banks[usize::from(a - 0x5120)] =
+                    u16::from(self.chr_upper) << 8 | u16::from(v);
+                self.chr_set = if a >= 0x5128 { 1 } else { 0 };
+            }
+            0x5130 => self.chr_upper = v & 0x03,
+            0x5200 => self.split = v,
+            0x5201 => self.split_scroll = v,
+            0x5202 => self.split_bank = v,
+            0x5203 => self.irq_compare = v,
+            0x5204 => self.irq_enabled = v & 0x80 != 0,
+            0x5205 => self.multiplicand = v,
+            0x5206 => self.multiplier = v,
+            0x5C00..=0x5FFF => match self.exram_mode {
+                // Only the PPU may use it while it's a nametable, out of frame zeros get in
+                0 | 1 => self.exram[a as usize - 0x5C00] = if self.in_frame { v } else { 0 },
+                2 => self.exram[a as usize - 0x5C00] = v,
+                _ => (),
+            },
+            0x6000..=0xFFFF => {
+                if let Prg::Ram(i) = self.prg_addr(a) {
+                    if self.ram_writable() && !self.prg_ram.is_empty() {
+                        let len = self.prg_ram.len();
+                        self.prg_ram[i % len] = v
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        let fetch = self.track(a);
+
+        match a {
+            0x0000..=0x1FFF => self.pattern_fetch(a, fetch),
+            0x2000..=0x3EFF => self.nametable_fetch(a, fetch),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a, self.chr_set);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let i = a as usize & 0x03FF;
+                match self.source(a) {
+                    0 => self.ciram[i] = v,
+                    1 => self.ciram[0x400 + i] = v,
+                    2 if self.exram_mode <= 1 => self.exram[i] = v,
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn ppu_register(&mut self, a: u16, v: u8) {
+        match a {
+            0x2000 => self.tall_sprites = v & 0x20 != 0,
+            0x2001 if v & 0x18 == 0 => self.in_frame = false,
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.idle = self.idle.saturating_add(1);
+        if self.idle >= IDLE_CYCLES {
+            self.in_frame = false;
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.nametables {
+            0x00 => Mirroring::SingleScreenLower,
+            0x44 => Mirroring::Vertical,
+            0x50 => Mirroring::Horizontal,
+            0x55 => Mirroring::SingleScreenUpper,
+            _ => Mirroring::MapperControlled,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending && self.irq_enabled
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
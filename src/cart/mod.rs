@@ -1,9 +1,259 @@
+pub mod axrom;
+pub mod cnrom;
+pub mod error;
+pub mod fme7;
+pub mod gxrom;
+pub mod header;
+pub mod mmc1;
+pub mod mmc2;
+pub mod mmc3;
+pub mod mmc5;
+pub mod n163;
 pub mod nrom;
+pub mod save;
+pub mod uxrom;
+mod vrc;
+pub mod vrc4;
+pub mod vrc6;
+pub mod vrc7;
 
-pub trait Mapper {
-    fn cpu_read(&self, a: u16) -> u8;
+use cart::axrom::AxROM;
+use cart::cnrom::CNROM;
+use cart::error::CartError;
+use cart::fme7::FME7;
+use cart::gxrom::GxROM;
+use cart::header::RomHeader;
+use cart::mmc1::MMC1;
+use cart::mmc2::{MMC2, MMC4};
+use cart::mmc3::MMC3;
+use cart::mmc5::MMC5;
+use cart::n163::N163;
+use cart::nrom::NROM;
+use cart::uxrom::UxROM;
+use cart::vrc4::VRC4;
+use cart::vrc6::VRC6;
+use cart::vrc7::VRC7;
+
+use std::ops::{Deref, DerefMut, Range};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    SingleScreenLower,
+    SingleScreenUpper,
+    FourScreen,
+    MapperControlled, // Nametables routed by the mapper itself through ppu_read
+}
+
+// The 2K of CIRAM in the console, or the 4K of cart VRAM of four-screen boards, laid out
+// as the mirroring asks. Addresses $3000-$3EFF mirror $2000-$2EFF.
+pub struct Nametables {
+    vram: Vec<u8>,
+}
+
+impl Nametables {
+    pub fn new(m: Mirroring) -> Nametables {
+        let size = match m {
+            Mirroring::FourScreen => 0x1000,
+            _ => 0x0800,
+        };
+        Nametables {
+            vram: vec![0; size],
+        }
+    }
+
+    fn addr(&self, m: Mirroring, a: u16) -> usize {
+        let table = usize::from((a >> 10) & 0x03);
+        let page = match m {
+            Mirroring::Horizontal => table >> 1,
+            Mirroring::Vertical => table & 0x01,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen | Mirroring::MapperControlled => table,
+        };
+
+        (page * 0x400 + (a as usize & 0x03FF)) % self.vram.len()
+    }
+
+    pub fn read(&self, m: Mirroring, a: u16) -> u8 {
+        self.vram[self.addr(m, a)]
+    }
+
+    pub fn write(&mut self, m: Mirroring, a: u16, v: u8) {
+        let i = self.addr(m, a);
+        self.vram[i] = v;
+    }
+}
+
+// PRG RAM at $6000-$7FFF and the part of it a battery keeps, which is what .sav files
+// hold. NES 2.0 NVRAM sits above the volatile RAM. Derefs to the RAM itself.
+pub struct PrgRam {
+    ram: Vec<u8>,
+    battery: Range<usize>,
+}
+
+impl PrgRam {
+    // `d` must be the dump `h` was parsed from
+    pub fn new(h: &RomHeader, d: &[u8]) -> PrgRam {
+        let ram = h.prg_ram(d);
+        let battery = match (h.battery, h.nes2) {
+            (false, _) => 0..0,
+            (true, true) => h.prg_ram_size..h.prg_ram_total(),
+            (true, false) => 0..ram.len(),
+        };
+
+        PrgRam { ram, battery }
+    }
+
+    // For boards without PRG RAM, which still get 8K at $6000 to hold a trainer
+    pub fn trainer(h: &RomHeader, d: &[u8]) -> PrgRam {
+        PrgRam {
+            ram: h.trainer(d).map_or(Vec::new(), |_| h.prg_ram(d)),
+            battery: 0..0,
+        }
+    }
+
+    pub fn battery(&self) -> Option<&[u8]> {
+        if self.battery.is_empty() {
+            None
+        } else {
+            Some(&self.ram[self.battery.clone()])
+        }
+    }
+
+    pub fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        if self.battery.is_empty() {
+            None
+        } else {
+            Some(&mut self.ram[self.battery.clone()])
+        }
+    }
+}
+
+impl Deref for PrgRam {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.ram
+    }
+}
+
+impl DerefMut for PrgRam {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+}
+
+// Send so that a CPU owning the bus and its cartridge can move between threads
+pub trait Mapper: Send {
+    // Side-effect free read, for debuggers and traces
+    fn cpu_peek(&self, a: u16) -> u8;
+    // Reading some registers acknowledges an IRQ
+    fn cpu_read(&mut self, a: u16) -> u8 {
+        self.cpu_peek(a)
+    }
+    // Bits of a read at `a` the cart drives, the others being open bus. By default only
+    // PRG ROM answers.
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
     fn cpu_write(&mut self, a: u16, v: u8);
-    fn ppu_read(&self, a: u16) -> u8;
+    // Address of the last CPU access the cart could not service, cleared once taken
+    fn fault(&mut self) -> Option<u16> {
+        None
+    }
+
+    // Whether writes to $8000-$FFFF reach registers. PRG ROM is never written, so the bus
+    // drops those writes on boards without any.
+    fn rom_registers(&self) -> bool {
+        true
+    }
+
+    // Whether the ROM keeps driving the data bus during those writes, which leaves the
+    // registers with the written value ANDed with the ROM byte
+    fn bus_conflicts(&self) -> bool {
+        false
+    }
+
+    // Overrides what the header said about conflicts, on boards that may have them
+    fn set_bus_conflicts(&mut self, _: bool) {}
+
+    // The value a register write at `a` in ROM space ends up latching
+    fn conflict(&self, a: u16, v: u8) -> u8 {
+        if self.bus_conflicts() {
+            v & self.cpu_peek(a)
+        } else {
+            v
+        }
+    }
+
+    // The whole PPU space below the palette, $0000-$3EFF: pattern tables then nametables.
+    // Mutable since some mappers switch banks as a side effect of PPU fetches.
+    fn ppu_read(&mut self, a: u16) -> u8;
     fn ppu_write(&mut self, a: u16, v: u8);
+
+    // Every address the PPU puts on its bus, fetched or not, for mappers watching A12
+    fn ppu_bus(&mut self, _: u16) {}
+
+    // Writes to the PPU registers at $2000-$2007, for mappers snooping the PPU setup
+    fn ppu_register(&mut self, _: u16, _: u8) {}
+
+    // Called once per CPU cycle, before that cycle's access
     fn cycle(&mut self);
+
+    fn mirroring(&self) -> Mirroring;
+
+    // IRQ output, held until the program acknowledges it
+    fn irq(&self) -> bool {
+        false
+    }
+
+    // Expansion audio, on the scale of the APU output it gets mixed with
+    fn audio(&self) -> f32 {
+        0.0
+    }
+
+    // Expansion audio registers for the chips left to an external core, like VRC7's FM
+    // synthesizer
+    fn audio_registers(&self) -> Option<&[u8]> {
+        None
+    }
+
+    // Battery-backed RAM, what .sav files hold. None for boards without a battery, the
+    // others hand over their PrgRam's.
+    fn battery(&self) -> Option<&[u8]> {
+        None
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        None
+    }
+}
+
+// Builds the mapper the header of an iNES or NES 2.0 dump asks for
+pub fn load(d: &[u8]) -> Result<Box<dyn Mapper>, CartError> {
+    let h = RomHeader::parse(d)?;
+
+    match (h.mapper, h.submapper) {
+        (0, _) => Ok(Box::new(NROM::from_header(&h, d))),
+        (1, _) => Ok(Box::new(MMC1::from_header(&h, d))),
+        (2, _) => Ok(Box::new(UxROM::from_header(&h, d))),
+        (3, _) => Ok(Box::new(CNROM::from_header(&h, d))),
+        (4, _) => Ok(Box::new(MMC3::from_header(&h, d))),
+        (5, _) => Ok(Box::new(MMC5::from_header(&h, d))),
+        (7, _) => Ok(Box::new(AxROM::from_header(&h, d))),
+        (9, _) => Ok(Box::new(MMC2::from_header(&h, d))),
+        (10, _) => Ok(Box::new(MMC4::from_header(&h, d))),
+        (19, _) => Ok(Box::new(N163::from_header(&h, d))),
+        (21, _) | (22, _) | (23, _) | (25, _) => Ok(Box::new(VRC4::from_header(&h, d))),
+        (24, _) | (26, _) => Ok(Box::new(VRC6::from_header(&h, d))),
+        (66, _) => Ok(Box::new(GxROM::from_header(&h, d))),
+        (69, _) => Ok(Box::new(FME7::from_header(&h, d))),
+        (85, _) => Ok(Box::new(VRC7::from_header(&h, d))),
+        (mapper, submapper) => Err(CartError::UnsupportedMapper { mapper, submapper }),
+    }
 }
@@ -0,0 +1,293 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// CPU cycles spent updating each wavetable channel in turn
+const CHANNEL_CYCLES: u8 = 15;
+
+// A channel at its peak is as loud as a full APU pulse channel
+const VOLUME: f32 = 0.1494 / 120.0;
+
+enum Ppu {
+    Chr(usize),
+    Ciram(usize),
+}
+
+// Mapper 19: three 8K PRG banks, 1K banks for the pattern tables and nametables which
+// pick either CHR ROM or CIRAM, a 15-bit IRQ counter and up to eight wavetable
+// channels playing from the 128 bytes of internal RAM
+pub struct N163 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    prg_ram: PrgRam,
+    ciram: [u8; 0x800],
+    ram: [u8; 0x80],
+
+    chr_banks: [u8; 12], // Eight pattern table banks, then the four nametables
+    prg_banks: [u8; 3],
+    ciram_disabled: u8, // Bits 6 and 7 keep CIRAM out of each pattern table
+    sound_disabled: bool,
+    address: u8, // Internal RAM address, auto-incremented when bit 7 is set
+    protect: u8,
+
+    irq_counter: u16,
+    irq_enabled: bool,
+    irq: bool,
+
+    audio_cycles: u8,
+    channel: usize,
+    outputs: [i8; 8],
+}
+
+impl N163 {
+    pub fn new(d: &[u8]) -> Result<N163, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(N163::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> N163 {
+        let chr_ram = h.chr_rom_size == 0;
+
+        N163 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            prg_ram: PrgRam::new(h, d),
+            ciram: [0; 0x800],
+            ram: [0; 0x80],
+            chr_banks: [0; 12],
+            prg_banks: [0; 3],
+            ciram_disabled: 0,
+            sound_disabled: false,
+            address: 0,
+            protect: 0,
+            irq_counter: 0,
+            irq_enabled: false,
+            irq: false,
+            audio_cycles: 0,
+            channel: 7,
+            outputs: [0; 8],
+        }
+    }
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> usize {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match a {
+            0xE000..=0xFFFF => banks - 1,
+            _ => usize::from(self.prg_banks[usize::from((a - 0x8000) >> 13)]),
+        };
+
+        ((bank % banks) * PRG_BANK_SIZE + (a as usize & 0x1FFF)) % self.prg_rom.len()
+    }
+
+    // Banks $E0 and up map a CIRAM page, unless disabled for that pattern table
+    fn ppu_addr(&self, a: u16) -> Ppu {
+        let v = match a {
+            0x0000..=0x1FFF => self.chr_banks[usize::from(a >> 10)],
+            _ => self.chr_banks[8 + usize::from((a >> 10) & 0x03)],
+        };
+        let ciram = match a {
+            0x0000..=0x0FFF => self.ciram_disabled & 0x40 == 0,
+            0x1000..=0x1FFF => self.ciram_disabled & 0x80 == 0,
+            _ => true,
+        };
+
+        if ciram && v >= 0xE0 {
+            Ppu::Ciram(usize::from(v & 0x01) * 0x400 + (a as usize & 0x03FF))
+        } else {
+            let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+            let i = (usize::from(v) % banks) * CHR_BANK_SIZE + (a as usize & 0x03FF);
+            Ppu::Chr(i % self.chr.len())
+        }
+    }
+
+    // Writes need bits 4-7 of $F800 to be 4 and the bit of their 2K window clear
+    fn ram_addr(&self, a: u16, write: bool) -> Option<usize> {
+        let i = a as usize - 0x6000;
+        let protected = self.protect & 0xF0 != 0x40 || self.protect & (1 << (i >> 11)) != 0;
+
+        if self.prg_ram.is_empty() || (write && protected) {
+            None
+        } else {
+            Some(i % self.prg_ram.len())
+        }
+    }
+    // #endregion
+
+    // #region Audio
+    fn step_address(&mut self) {
+        if self.address & 0x80 != 0 {
+            self.address = 0x80 | (self.address.wrapping_add(1) & 0x7F);
+        }
+    }
+
+    fn channels(&self) -> usize {
+        usize::from((self.ram[0x7F] >> 4) & 0x07) + 1
+    }
+
+    // Advances the phase of one channel and samples its wave
+    fn update(&mut self, ch: usize) {
+        let b = 0x40 + ch * 8;
+        let r = &mut self.ram;
+
+        let freq = u32::from(r[b]) | u32::from(r[b + 2]) << 8 | u32::from(r[b + 4] & 0x03) << 16;
+        let phase = u32::from(r[b + 1]) | u32::from(r[b + 3]) << 8 | u32::from(r[b + 5]) << 16;
+        let length = 256 - u32::from(r[b + 4] & 0xFC);
+
+        let phase = (phase + freq) % (length << 16);
+        r[b + 1] = phase as u8;
+        r[b + 3] = (phase >> 8) as u8;
+        r[b + 5] = (phase >> 16) as u8;
+
+        let sample = ((phase >> 16) as u8).wrapping_add(r[b + 6]);
+        let nibble = (r[usize::from(sample >> 1) & 0x7F] >> ((sample & 0x01) * 4)) & 0x0F;
+        self.outputs[ch] = (nibble as i8 - 8) * (r[b + 7] & 0x0F) as i8;
+    }
+    // #endregion
+}
+
+impl Mapper for N163 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x4800..=0x4FFF => self.ram[usize::from(self.address & 0x7F)],
+            0x5000..=0x57FF => self.irq_counter as u8,
+            0x5800..=0x5FFF => (self.irq_counter >> 8) as u8 | u8::from(self.irq_enabled) << 7,
+            0x6000..=0x7FFF => self.ram_addr(a, false).map_or(0, |i| self.prg_ram[i]),
+            0x8000..=0xFFFF => self.prg_rom[self.prg_addr(a)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x4800..=0x5FFF => 0xFF,
+            0x6000..=0x7FFF if self.ram_addr(a, false).is_some() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_read(&mut self, a: u16) -> u8 {
+        let v = self.cpu_peek(a);
+        if let 0x4800..=0x4FFF = a {
+            self.step_address();
+        }
+        v
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x4800..=0x4FFF => {
+                self.ram[usize::from(self.address & 0x7F)] = v;
+                self.step_address();
+            }
+            0x5000..=0x57FF => {
+                self.irq_counter = (self.irq_counter & 0x7F00) | u16::from(v);
+                self.irq = false;
+            }
+            0x5800..=0x5FFF => {
+                self.irq_counter = (self.irq_counter & 0x00FF) | u16::from(v & 0x7F) << 8;
+                self.irq_enabled = v & 0x80 != 0;
+                self.irq = false;
+            }
+            0x6000..=0x7FFF => {
+                if let Some(i) = self.ram_addr(a, true) {
+                    self.prg_ram[i] = v
+                }
+            }
+            0x8000..=0xDFFF => self.chr_banks[usize::from((a - 0x8000) >> 11)] = v,
+            0xE000..=0xE7FF => {
+                self.prg_banks[0] = v & 0x3F;
+                self.sound_disabled = v & 0x40 != 0;
+            }
+            0xE800..=0xEFFF => {
+                self.prg_banks[1] = v & 0x3F;
+                self.ciram_disabled = v & 0xC0;
+            }
+            0xF000..=0xF7FF => self.prg_banks[2] = v & 0x3F,
+            0xF800..=0xFFFF => {
+                self.address = v;
+                self.protect = v;
+            }
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match (a, self.ppu_addr(a)) {
+            (0x0000..=0x3EFF, Ppu::Chr(i)) => self.chr[i],
+            (0x0000..=0x3EFF, Ppu::Ciram(i)) => self.ciram[i],
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match (a, self.ppu_addr(a)) {
+            (0x0000..=0x3EFF, Ppu::Ciram(i)) => self.ciram[i] = v,
+            (0x0000..=0x1FFF, Ppu::Chr(i)) if self.chr_ram => self.chr[i] = v,
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        if self.irq_enabled && self.irq_counter < 0x7FFF {
+            self.irq_counter += 1;
+            if self.irq_counter == 0x7FFF {
+                self.irq = true;
+            }
+        }
+
+        self.audio_cycles += 1;
+        if self.audio_cycles == CHANNEL_CYCLES {
+            self.audio_cycles = 0;
+
+            // Channels run from 7 down to the lowest enabled one
+            let ch = self.channel;
+            self.update(ch);
+            self.channel = if ch <= 8 - self.channels() { 7 } else { ch - 1 };
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        let n = &self.chr_banks[8..];
+
+        if n.iter().any(|&v| v < 0xE0) {
+            return Mirroring::MapperControlled;
+        }
+        match (n[0] & 0x01, n[1] & 0x01, n[2] & 0x01, n[3] & 0x01) {
+            (0, 0, 0, 0) => Mirroring::SingleScreenLower,
+            (1, 1, 1, 1) => Mirroring::SingleScreenUpper,
+            (0, 1, 0, 1) => Mirroring::Vertical,
+            (0, 0, 1, 1) => Mirroring::Horizontal,
+            _ => Mirroring::MapperControlled,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    // The chip plays the channels one after the other, which averages them
+    fn audio(&self) -> f32 {
+        if self.sound_disabled {
+            return 0.0;
+        }
+
+        let n = self.channels();
+        let sum: i32 = self.outputs[8 - n..].iter().map(|&v| i32::from(v)).sum();
+        sum as f32 / n as f32 * VOLUME
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
@@ -1,65 +1,93 @@
-use cart::Mapper;
-
-const PRG_ROM_PAGE_SIZE: u16 = 1_6384;
-const CHR_ROM_PAGE_SIZE: u16 = 8192;
-const PRG_RAM_PAGE_SIZE: u16 = 8192;
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
 
 pub struct NROM {
     prg_rom: Vec<u8>,
-    chr_rom: Vec<u8>,
-    prg_ram: Vec<u8>,
-    prg_num: u8,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
 }
 
 impl NROM {
-    pub fn new(d: &[u8]) -> NROM {
-        let prg_num = d[4];
-        let prg_size = u16::from(prg_num) * PRG_ROM_PAGE_SIZE;
-        let chr_size = u16::from(d[5])   * CHR_ROM_PAGE_SIZE;
-        let ram_size = if d[8] == 0 {
-            PRG_RAM_PAGE_SIZE
-        } else {
-           u16::from(d[8]) * PRG_RAM_PAGE_SIZE
-        };
+    pub fn new(d: &[u8]) -> Result<NROM, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(NROM::from_header(&h, d))
+    }
 
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> NROM {
         NROM {
-            prg_rom: d[16..(16 + prg_size) as usize].to_vec(),
-            chr_rom: d[(16 + prg_size) as usize..(16 + prg_size + chr_size) as usize].to_vec(),
-            prg_ram: vec![0; ram_size as usize],
-            prg_num,
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram: h.chr_rom_size == 0,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            mirroring: h.mirroring,
         }
     }
 }
 
 impl Mapper for NROM {
-    fn cpu_read(&self, a: u16) -> u8 {
+    fn cpu_peek(&self, a: u16) -> u8 {
         match a {
-            0x6000...0x7FFF => self.prg_ram[a as usize % 0x6000],
-            0x8000...0xBFFF => self.prg_rom[a as usize % 0x8000],
-            0xC000...0xFFFF => match self.prg_num {
-                2 => self.prg_rom[a as usize % 0x8000],
-                _ => self.prg_rom[a as usize % 0xC000],
-            },
+            0x6000..=0x7FFF => self.prg_ram.get(a as usize % 0x6000).cloned().unwrap_or(0),
+            0x8000..=0xFFFF => self.prg_rom[(a as usize - 0x8000) % self.prg_rom.len()],
             _ => 0,
         }
     }
 
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
     fn cpu_write(&mut self, a: u16, v: u8) {
+        if let 0x6000..=0x7FFF = a {
+            if let Some(b) = self.prg_ram.get_mut(a as usize % 0x6000) {
+                *b = v
+            }
+        }
+    }
+
+    fn rom_registers(&self) -> bool {
+        false
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
         match a {
-            0x6000...0x7FFF => self.prg_ram[a as usize % 0x6000] = v,
-            0x8000...0xBFFF => self.prg_rom[a as usize % 0x8000] = v,
-            0xC000...0xFFFF => match self.prg_num {
-                2 => self.prg_rom[a as usize % 0x8000] = v,
-                _ => self.prg_rom[a as usize % 0xC000] = v,
-            },
-            _ => (),
+            0x0000..=0x1FFF => self.chr[a as usize % self.chr.len()],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring, a),
+            _ => 0,
         }
     }
 
-    fn ppu_read(&self, _: u16) -> u8 {
-        0
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let len = self.chr.len();
+                self.chr[a as usize % len] = v
+            }
+            0x2000..=0x3EFF => self.nametables.write(self.mirroring, a, v),
+            _ => (),
+        }
     }
 
-    fn ppu_write(&mut self, _: u16, _: u8) {}
     fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
 }
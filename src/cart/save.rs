@@ -0,0 +1,60 @@
+use cart::error::CartError;
+use cart::Mapper;
+
+// Copy of the battery-backed RAM, to write out as a .sav file
+pub fn export(m: &dyn Mapper) -> Option<Vec<u8>> {
+    m.battery().map(|r| r.to_vec())
+}
+
+// Restores a .sav file, which must be exactly the size of the RAM
+pub fn import(m: &mut dyn Mapper, d: &[u8]) -> Result<(), CartError> {
+    let r = m.battery_mut().ok_or(CartError::NoBattery)?;
+
+    if r.len() != d.len() {
+        return Err(CartError::SaveSize {
+            expected: r.len(),
+            found: d.len(),
+        });
+    }
+    r.copy_from_slice(d);
+    Ok(())
+}
+
+// Hands the battery-backed RAM to `hook` whenever it changed, checking every `interval`
+// ticks. Frontends tick it once a frame, and flush it on exit.
+pub struct Autosave<F: FnMut(&[u8])> {
+    hook: F,
+    interval: u32,
+    ticks: u32,
+    saved: Vec<u8>,
+}
+
+impl<F: FnMut(&[u8])> Autosave<F> {
+    // Starts from the current RAM, so a save just imported isn't written back
+    pub fn new(m: &dyn Mapper, interval: u32, hook: F) -> Autosave<F> {
+        Autosave {
+            hook,
+            interval: interval.max(1),
+            ticks: 0,
+            saved: export(m).unwrap_or_default(),
+        }
+    }
+
+    pub fn tick(&mut self, m: &dyn Mapper) {
+        self.ticks += 1;
+        if self.ticks >= self.interval {
+            self.flush(m);
+        }
+    }
+
+    pub fn flush(&mut self, m: &dyn Mapper) {
+        self.ticks = 0;
+
+        if let Some(r) = m.battery() {
+            if r != &self.saved[..] {
+                (self.hook)(r);
+                self.saved = r.to_vec();
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+
+// Mapper 2: switchable 16K bank at $8000, last bank fixed at $C000, 8K of CHR RAM
+pub struct UxROM {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
+    bus_conflicts: bool,
+    bank: u8,
+}
+
+impl UxROM {
+    pub fn new(d: &[u8]) -> Result<UxROM, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(UxROM::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> UxROM {
+        let chr_ram = h.chr_rom_size == 0;
+
+        UxROM {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::trainer(h, d),
+            mirroring: h.mirroring,
+            bus_conflicts: h.bus_conflicts(),
+            bank: 0,
+        }
+    }
+}
+
+impl Mapper for UxROM {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match a {
+            0x6000..=0x7FFF => {
+                return self.prg_ram.get(a as usize - 0x6000).cloned().unwrap_or(0);
+            }
+            0x8000..=0xBFFF => usize::from(self.bank) % banks,
+            0xC000..=0xFFFF => banks - 1,
+            _ => return 0,
+        };
+
+        self.prg_rom[(bank * PRG_BANK_SIZE + (a as usize & 0x3FFF)) % self.prg_rom.len()]
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x6000..=0x7FFF => {
+                if let Some(b) = self.prg_ram.get_mut(a as usize - 0x6000) {
+                    *b = v
+                }
+            }
+            0x8000..=0xFFFF => self.bank = self.conflict(a, v),
+            _ => (),
+        }
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        self.bus_conflicts
+    }
+
+    fn set_bus_conflicts(&mut self, on: bool) {
+        self.bus_conflicts = on;
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[a as usize % self.chr.len()],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let len = self.chr.len();
+                self.chr[a as usize % len] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
@@ -0,0 +1,102 @@
+// Pieces shared by the Konami VRC chips
+
+// CPU address lines a board wires to the chip's two register select inputs
+#[derive(Clone, Copy)]
+pub struct Wiring(pub u16, pub u16);
+
+impl Wiring {
+    // $x000-$x003 as the chip sees it
+    pub fn reg(self, a: u16) -> u16 {
+        let a0 = if a & self.0 != 0 { 0x01 } else { 0x00 };
+        let a1 = if a & self.1 != 0 { 0x02 } else { 0x00 };
+        (a & 0xF000) | a0 | a1
+    }
+}
+
+// CPU cycles per scanline, times 3 so the prescaler stays whole
+const PRESCALER: i16 = 341;
+
+// Counts up from the latch to $FF, either every CPU cycle or every scanline's worth
+// of them, and raises an IRQ when it wraps
+pub struct Irq {
+    latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enabled: bool,
+    enabled_after_ack: bool,
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl Irq {
+    pub fn new() -> Irq {
+        Irq {
+            latch: 0,
+            counter: 0,
+            prescaler: PRESCALER,
+            enabled: false,
+            enabled_after_ack: false,
+            cycle_mode: false,
+            pending: false,
+        }
+    }
+
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    pub fn set_latch(&mut self, v: u8) {
+        self.latch = v;
+    }
+
+    // VRC4 takes the latch a nibble at a time
+    pub fn set_latch_low(&mut self, v: u8) {
+        self.latch = (self.latch & 0xF0) | (v & 0x0F);
+    }
+
+    pub fn set_latch_high(&mut self, v: u8) {
+        self.latch = (self.latch & 0x0F) | (v << 4);
+    }
+
+    pub fn control(&mut self, v: u8) {
+        self.enabled_after_ack = v & 0x01 != 0;
+        self.enabled = v & 0x02 != 0;
+        self.cycle_mode = v & 0x04 != 0;
+        self.pending = false;
+
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER;
+        }
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enabled_after_ack;
+    }
+
+    pub fn cycle(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.cycle_mode {
+            self.clock();
+        } else {
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += PRESCALER;
+                self.clock();
+            }
+        }
+    }
+
+    fn clock(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+}
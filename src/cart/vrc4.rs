@@ -0,0 +1,215 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::vrc::{Irq, Wiring};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// VRC2 is the IRQ-less predecessor, with one mirroring bit and no PRG swap mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chip {
+    Vrc2,
+    Vrc4,
+}
+
+// Mappers 21, 22, 23 and 25, which only differ in how the boards wire the address lines.
+// Submappers name the board, otherwise the lines of every board of the mapper are used.
+fn detect(h: &RomHeader) -> (Chip, Wiring) {
+    match (h.mapper, h.submapper) {
+        (21, 1) => (Chip::Vrc4, Wiring(0x02, 0x04)), // VRC4a
+        (21, 2) => (Chip::Vrc4, Wiring(0x40, 0x80)), // VRC4c
+        (21, _) => (Chip::Vrc4, Wiring(0x42, 0x84)),
+        (22, _) => (Chip::Vrc2, Wiring(0x02, 0x01)), // VRC2a
+        (23, 1) => (Chip::Vrc4, Wiring(0x01, 0x02)), // VRC4f
+        (23, 2) => (Chip::Vrc4, Wiring(0x04, 0x08)), // VRC4e
+        (23, 3) => (Chip::Vrc2, Wiring(0x01, 0x02)), // VRC2b
+        (25, 1) => (Chip::Vrc4, Wiring(0x02, 0x01)), // VRC4b
+        (25, 2) => (Chip::Vrc4, Wiring(0x08, 0x04)), // VRC4d
+        (25, 3) => (Chip::Vrc2, Wiring(0x02, 0x01)), // VRC2c
+        (25, _) => (Chip::Vrc4, Wiring(0x0A, 0x05)),
+        (_, _) => (Chip::Vrc4, Wiring(0x05, 0x0A)),
+    }
+}
+
+pub struct VRC4 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    chip: Chip,
+    wiring: Wiring,
+    chr_shift: u8, // VRC2a drops the lowest CHR bank bit
+
+    prg_banks: [u8; 2],
+    prg_swap: bool,
+    chr_banks: [u16; 8],
+    mirroring: u8,
+    latch: u8, // What VRC2 boards without PRG RAM keep at $6000
+    irq: Irq,
+}
+
+impl VRC4 {
+    pub fn new(d: &[u8]) -> Result<VRC4, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(VRC4::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> VRC4 {
+        let chr_ram = h.chr_rom_size == 0;
+        let (chip, wiring) = detect(h);
+
+        VRC4 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            chip,
+            wiring,
+            chr_shift: if h.mapper == 22 { 1 } else { 0 },
+            prg_banks: [0; 2],
+            prg_swap: false,
+            chr_banks: [0; 8],
+            mirroring: 0,
+            latch: 0,
+            irq: Irq::new(),
+        }
+    }
+
+    pub fn chip(&self) -> Chip {
+        self.chip
+    }
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> usize {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+
+        let bank = match (a >> 13) & 0x03 {
+            0 if self.prg_swap => banks.saturating_sub(2),
+            0 => usize::from(self.prg_banks[0]),
+            1 => usize::from(self.prg_banks[1]),
+            2 if self.prg_swap => usize::from(self.prg_banks[0]),
+            2 => banks.saturating_sub(2),
+            _ => banks - 1,
+        };
+
+        ((bank % banks) * PRG_BANK_SIZE + (a as usize & 0x1FFF)) % self.prg_rom.len()
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        let bank = usize::from(self.chr_banks[usize::from(a >> 10)] >> self.chr_shift);
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((bank % banks) * CHR_BANK_SIZE + (a as usize & 0x03FF)) % self.chr.len()
+    }
+
+    // $B000-$E003, the low then the high nibble of each 1K bank
+    fn chr_write(&mut self, r: u16, v: u8) {
+        let i = usize::from(((r >> 12) - 0x0B) * 2 + ((r >> 1) & 0x01));
+        let b = &mut self.chr_banks[i];
+
+        *b = if r & 0x01 == 0 {
+            (*b & 0x1F0) | u16::from(v & 0x0F)
+        } else {
+            (*b & 0x00F) | u16::from(v & 0x1F) << 4
+        };
+    }
+    // #endregion
+}
+
+impl Mapper for VRC4 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.prg_ram.is_empty() && self.chip == Chip::Vrc2 => self.latch,
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => {
+                self.prg_ram[(a as usize - 0x6000) % self.prg_ram.len()]
+            }
+            0x8000..=0xFFFF => self.prg_rom[self.prg_addr(a)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => 0xFF,
+            0x6000..=0x7FFF if self.chip == Chip::Vrc2 => 0x01,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        if let 0x6000..=0x7FFF = a {
+            if self.prg_ram.is_empty() {
+                self.latch = v & 0x01;
+            } else {
+                let len = self.prg_ram.len();
+                self.prg_ram[(a as usize - 0x6000) % len] = v;
+            }
+            return;
+        }
+
+        let r = self.wiring.reg(a);
+        match (self.chip, r) {
+            (_, 0x8000..=0x8003) => self.prg_banks[0] = v & 0x1F,
+            (Chip::Vrc2, 0x9000..=0x9003) => self.mirroring = v & 0x01,
+            (Chip::Vrc4, 0x9000..=0x9001) => self.mirroring = v & 0x03,
+            (Chip::Vrc4, 0x9002..=0x9003) => self.prg_swap = v & 0x02 != 0,
+            (_, 0xA000..=0xA003) => self.prg_banks[1] = v & 0x1F,
+            (_, 0xB000..=0xE003) => self.chr_write(r, v),
+            (Chip::Vrc4, 0xF000) => self.irq.set_latch_low(v),
+            (Chip::Vrc4, 0xF001) => self.irq.set_latch_high(v),
+            (Chip::Vrc4, 0xF002) => self.irq.control(v),
+            (Chip::Vrc4, 0xF003) => self.irq.acknowledge(),
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.irq.cycle();
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.mirroring {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
@@ -0,0 +1,340 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::vrc::{Irq, Wiring};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// Output of a pulse channel at full volume matches a full APU pulse channel
+const VOLUME: f32 = 0.1494 / 15.0;
+
+// #region Audio
+struct Pulse {
+    volume: u8,
+    duty: u8,
+    constant: bool, // Ignores the duty cycle and outputs the volume all the time
+    period: u16,
+    enabled: bool,
+    divider: u16,
+    step: u8,
+}
+
+impl Pulse {
+    fn new() -> Pulse {
+        Pulse {
+            volume: 0,
+            duty: 0,
+            constant: false,
+            period: 0,
+            enabled: false,
+            divider: 0,
+            step: 15,
+        }
+    }
+
+    fn write(&mut self, r: u16, v: u8) {
+        match r & 0x03 {
+            0 => {
+                self.volume = v & 0x0F;
+                self.duty = (v >> 4) & 0x07;
+                self.constant = v & 0x80 != 0;
+            }
+            1 => self.period = (self.period & 0x0F00) | u16::from(v),
+            _ => {
+                self.period = (self.period & 0x00FF) | u16::from(v & 0x0F) << 8;
+                self.enabled = v & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 15;
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.divider == 0 {
+            self.divider = self.period >> shift;
+            self.step = self.step.wrapping_sub(1) & 0x0F;
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        if self.enabled && (self.constant || self.step <= self.duty) {
+            self.volume
+        } else {
+            0
+        }
+    }
+}
+
+struct Saw {
+    rate: u8,
+    period: u16,
+    enabled: bool,
+    divider: u16,
+    step: u8,
+    accumulator: u8,
+}
+
+impl Saw {
+    fn new() -> Saw {
+        Saw {
+            rate: 0,
+            period: 0,
+            enabled: false,
+            divider: 0,
+            step: 0,
+            accumulator: 0,
+        }
+    }
+
+    fn write(&mut self, r: u16, v: u8) {
+        match r & 0x03 {
+            0 => self.rate = v & 0x3F,
+            1 => self.period = (self.period & 0x0F00) | u16::from(v),
+            _ => {
+                self.period = (self.period & 0x00FF) | u16::from(v & 0x0F) << 8;
+                self.enabled = v & 0x80 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            }
+        }
+    }
+
+    // The rate is added on every other step and the accumulator cleared on the 14th
+    fn clock(&mut self, shift: u8) {
+        if !self.enabled {
+            return;
+        }
+
+        if self.divider > 0 {
+            self.divider -= 1;
+            return;
+        }
+
+        self.divider = self.period >> shift;
+        self.step += 1;
+        if self.step == 14 {
+            self.step = 0;
+            self.accumulator = 0;
+        } else if self.step & 0x01 == 0 {
+            self.accumulator = self.accumulator.wrapping_add(self.rate);
+        }
+    }
+
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
+// #endregion
+
+// Mappers 24 and 26: 16K and 8K switchable PRG banks, 1K CHR banks, two pulse
+// channels and a sawtooth
+pub struct VRC6 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    wiring: Wiring,
+
+    prg_banks: [u8; 2],
+    chr_banks: [u8; 8],
+    control: u8, // $B003: CHR layout, mirroring and PRG RAM enable
+    irq: Irq,
+
+    pulses: [Pulse; 2],
+    saw: Saw,
+    halt: bool,
+    shift: u8, // Divides all the periods by 16 or 256
+}
+
+impl VRC6 {
+    pub fn new(d: &[u8]) -> Result<VRC6, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(VRC6::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from. Mapper 26 swaps A0 and A1.
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> VRC6 {
+        let chr_ram = h.chr_rom_size == 0;
+
+        VRC6 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            wiring: if h.mapper == 26 {
+                Wiring(0x02, 0x01)
+            } else {
+                Wiring(0x01, 0x02)
+            },
+            prg_banks: [0; 2],
+            chr_banks: [0; 8],
+            control: 0,
+            irq: Irq::new(),
+            pulses: [Pulse::new(), Pulse::new()],
+            saw: Saw::new(),
+            halt: false,
+            shift: 0,
+        }
+    }
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> usize {
+        let (bank, size) = match a {
+            0x8000..=0xBFFF => (usize::from(self.prg_banks[0]) * 2, 0x4000),
+            0xC000..=0xDFFF => (usize::from(self.prg_banks[1]), 0x2000),
+            _ => ((self.prg_rom.len() / 0x2000).saturating_sub(1), 0x2000),
+        };
+
+        (bank * 0x2000 + (a as usize & (size - 1))) % self.prg_rom.len()
+    }
+
+    // Modes 0 to 3 give 1K banks, 2K banks from R0-R3, or 1K banks below $1000 and
+    // 2K banks from R4 and R5 above. The registers hold 1K pages either way, in a 2K
+    // bank bit 5 swaps their low bit for PPU A10.
+    fn chr_addr(&self, a: u16) -> usize {
+        let a = a as usize;
+        let r = &self.chr_banks;
+
+        let (page, wide) = match (self.control & 0x03, a >> 10) {
+            (0, n) => (usize::from(r[n]), false),
+            (1, n) => (usize::from(r[n / 2]), true),
+            (_, n) if n < 4 => (usize::from(r[n]), false),
+            (_, n) => (usize::from(r[4 + (n - 4) / 2]), true),
+        };
+        let page = if wide && self.control & 0x20 != 0 {
+            (page & !1) | ((a >> 10) & 1)
+        } else {
+            page
+        };
+
+        (page * CHR_BANK_SIZE + a % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    fn ram_enabled(&self) -> bool {
+        self.control & 0x80 != 0 && !self.prg_ram.is_empty()
+    }
+    // #endregion
+}
+
+impl Mapper for VRC6 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.ram_enabled() => {
+                self.prg_ram[(a as usize - 0x6000) % self.prg_ram.len()]
+            }
+            0x8000..=0xFFFF => self.prg_rom[self.prg_addr(a)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.ram_enabled() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        if let 0x6000..=0x7FFF = a {
+            if self.ram_enabled() {
+                let len = self.prg_ram.len();
+                self.prg_ram[(a as usize - 0x6000) % len] = v;
+            }
+            return;
+        }
+
+        let r = self.wiring.reg(a);
+        match r {
+            0x8000..=0x8003 => self.prg_banks[0] = v & 0x0F,
+            0x9003 => {
+                self.halt = v & 0x01 != 0;
+                self.shift = match v & 0x06 {
+                    0 => 0,
+                    0x02 => 4,
+                    _ => 8,
+                };
+            }
+            0x9000..=0x9002 => self.pulses[0].write(r, v),
+            0xA000..=0xA002 => self.pulses[1].write(r, v),
+            0xB000..=0xB002 => self.saw.write(r, v),
+            0xB003 => self.control = v,
+            0xC000..=0xC003 => self.prg_banks[1] = v & 0x1F,
+            0xD000..=0xE003 => {
+                self.chr_banks[usize::from(((r >> 12) - 0x0D) * 4 + (r & 0x03))] = v;
+            }
+            0xF000 => self.irq.set_latch(v),
+            0xF001 => self.irq.control(v),
+            0xF002 => self.irq.acknowledge(),
+            _ => (),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.irq.cycle();
+
+        if !self.halt {
+            self.pulses[0].clock(self.shift);
+            self.pulses[1].clock(self.shift);
+            self.saw.clock(self.shift);
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match (self.control >> 2) & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.pending()
+    }
+
+    fn audio(&self) -> f32 {
+        let sum = self.pulses[0].output() + self.pulses[1].output() + self.saw.output();
+        f32::from(sum) * VOLUME
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
@@ -0,0 +1,194 @@
+use cart::header::{HeaderError, RomHeader};
+use cart::vrc::{Irq, Wiring};
+use cart::{Mapper, Mirroring, Nametables, PrgRam};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+// Mapper 85: three 8K PRG banks, 1K CHR banks and a YM2413-derived FM synthesizer.
+// The synthesizer isn't emulated; its registers are kept for an external OPLL core.
+pub struct VRC7 {
+    prg_rom: Vec<u8>,
+    chr: Vec<u8>,
+    chr_ram: bool,
+    nametables: Nametables,
+    prg_ram: PrgRam,
+    wiring: Wiring,
+
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    control: u8, // $E000: mirroring, audio reset and PRG RAM enable
+    irq: Irq,
+
+    audio_select: u8,
+    audio: [u8; 0x40],
+}
+
+impl VRC7 {
+    pub fn new(d: &[u8]) -> Result<VRC7, HeaderError> {
+        let h = RomHeader::parse(d)?;
+        Ok(VRC7::from_header(&h, d))
+    }
+
+    // `d` must be the dump `h` was parsed from. Submapper 1 is VRC7b with A3 selecting
+    // the second register of each pair, 2 is VRC7a with A4.
+    pub fn from_header(h: &RomHeader, d: &[u8]) -> VRC7 {
+        let chr_ram = h.chr_rom_size == 0;
+        let line = match h.submapper {
+            1 => 0x08,
+            2 => 0x10,
+            _ => 0x18,
+        };
+
+        VRC7 {
+            prg_rom: h.prg_rom(d).to_vec(),
+            chr: h.chr(d),
+            chr_ram,
+            nametables: Nametables::new(h.mirroring),
+            prg_ram: PrgRam::new(h, d),
+            wiring: Wiring(line, 0),
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            control: 0,
+            irq: Irq::new(),
+            audio_select: 0,
+            audio: [0; 0x40],
+        }
+    }
+
+    // #region Banking
+    fn prg_addr(&self, a: u16) -> usize {
+        let banks = (self.prg_rom.len() / PRG_BANK_SIZE).max(1);
+        let bank = match a {
+            0x8000..=0xDFFF => usize::from(self.prg_banks[usize::from((a - 0x8000) >> 13)]),
+            _ => banks - 1,
+        };
+
+        ((bank % banks) * PRG_BANK_SIZE + (a as usize & 0x1FFF)) % self.prg_rom.len()
+    }
+
+    fn chr_addr(&self, a: u16) -> usize {
+        let bank = usize::from(self.chr_banks[usize::from(a >> 10)]);
+        let banks = (self.chr.len() / CHR_BANK_SIZE).max(1);
+        ((bank % banks) * CHR_BANK_SIZE + (a as usize & 0x03FF)) % self.chr.len()
+    }
+
+    fn ram_enabled(&self) -> bool {
+        self.control & 0x80 != 0 && !self.prg_ram.is_empty()
+    }
+    // #endregion
+
+    // The wiring puts the second register of each pair at $x001
+    fn register(&mut self, a: u16, v: u8) {
+        let r = self.wiring.reg(a);
+        let second = usize::from(r & 0x01);
+
+        match r {
+            0x8000 | 0x8001 => self.prg_banks[second] = v & 0x3F,
+            0x9000 => self.prg_banks[2] = v & 0x3F,
+            0xA000..=0xD001 => self.chr_banks[usize::from((r >> 12) - 0x0A) * 2 + second] = v,
+            0xE000 => {
+                self.control = v;
+                if v & 0x40 != 0 {
+                    self.audio = [0; 0x40];
+                }
+            }
+            0xE001 => self.irq.set_latch(v),
+            0xF000 => self.irq.control(v),
+            0xF001 => self.irq.acknowledge(),
+            _ => (),
+        }
+    }
+}
+
+impl Mapper for VRC7 {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.ram_enabled() => {
+                self.prg_ram[(a as usize - 0x6000) % self.prg_ram.len()]
+            }
+            0x8000..=0xFFFF => self.prg_rom[self.prg_addr(a)],
+            _ => 0,
+        }
+    }
+
+    fn cpu_driven(&self, a: u16) -> u8 {
+        match a {
+            0x6000..=0x7FFF if self.ram_enabled() => 0xFF,
+            0x8000..=0xFFFF => 0xFF,
+            _ => 0x00,
+        }
+    }
+
+    fn cpu_write(&mut self, a: u16, v: u8) {
+        if let 0x6000..=0x7FFF = a {
+            if self.ram_enabled() {
+                let len = self.prg_ram.len();
+                self.prg_ram[(a as usize - 0x6000) % len] = v;
+            }
+            return;
+        }
+
+        // The audio ports decode A4 and A5 on every board
+        match a & 0xF030 {
+            0x9010 => self.audio_select = v & 0x3F,
+            0x9030 if self.control & 0x40 == 0 => {
+                self.audio[usize::from(self.audio_select)] = v;
+            }
+            0x9030 => (),
+            _ => self.register(a, v),
+        }
+    }
+
+    fn ppu_read(&mut self, a: u16) -> u8 {
+        match a {
+            0x0000..=0x1FFF => self.chr[self.chr_addr(a)],
+            0x2000..=0x3EFF => self.nametables.read(self.mirroring(), a),
+            _ => 0,
+        }
+    }
+
+    fn ppu_write(&mut self, a: u16, v: u8) {
+        match a {
+            0x0000..=0x1FFF if self.chr_ram => {
+                let i = self.chr_addr(a);
+                self.chr[i] = v
+            }
+            0x2000..=0x3EFF => {
+                let m = self.mirroring();
+                self.nametables.write(m, a, v)
+            }
+            _ => (),
+        }
+    }
+
+    fn cycle(&mut self) {
+        self.irq.cycle();
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::Vertical,
+            1 => Mirroring::Horizontal,
+            2 => Mirroring::SingleScreenLower,
+            _ => Mirroring::SingleScreenUpper,
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq.pending()
+    }
+
+    // Registers of the FM synthesizer, cleared while $E000 holds it in reset
+    fn audio_registers(&self) -> Option<&[u8]> {
+        Some(&self.audio)
+    }
+
+    fn battery(&self) -> Option<&[u8]> {
+        self.prg_ram.battery()
+    }
+
+    fn battery_mut(&mut self) -> Option<&mut [u8]> {
+        self.prg_ram.battery_mut()
+    }
+}
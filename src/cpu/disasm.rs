@@ -0,0 +1,96 @@
+use std::fmt;
+
+use cpu::ops::Operation;
+use cpu::table::{Mode, OPCODES};
+use MMU;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub operation: Operation,
+    pub mnemonic: &'static str,
+    pub operand: String,
+    pub official: bool,
+}
+
+impl Instruction {
+    pub fn len(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    // Address of the instruction that follows this one
+    pub fn next(&self) -> u16 {
+        self.address.wrapping_add(self.len())
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.official {
+            write!(f, "*")?;
+        }
+
+        if self.operand.is_empty() {
+            write!(f, "{}", self.mnemonic)
+        } else {
+            write!(f, "{} {}", self.mnemonic, self.operand)
+        }
+    }
+}
+
+// Decodes the instruction starting at `d[0]`, located at `address` in CPU space.
+// Returns None when `d` is too short to hold all of its operand bytes.
+pub fn disassemble(d: &[u8], address: u16) -> Option<Instruction> {
+    let opcode = *d.first()?;
+    let info = &OPCODES[opcode as usize];
+    let bytes = d.get(..info.length as usize)?.to_vec();
+
+    Some(Instruction {
+        address,
+        operand: operand(info.mode, &bytes, address),
+        mnemonic: info.mnemonic,
+        official: info.official,
+        operation: opcode.into(),
+        bytes,
+    })
+}
+
+pub fn disassemble_at<M: MMU + ?Sized>(bus: &M, address: u16) -> Instruction {
+    let d = [
+        bus.peek(address),
+        bus.peek(address.wrapping_add(1)),
+        bus.peek(address.wrapping_add(2)),
+    ];
+
+    disassemble(&d, address).unwrap()
+}
+
+fn operand(mode: Mode, d: &[u8], address: u16) -> String {
+    use self::Mode::*;
+
+    let w = || u16::from(d[1]) | (u16::from(d[2]) << 8);
+
+    match mode {
+        Implied => String::new(),
+        Accumulator => "A".to_string(),
+        Immediate => format!("#${:02X}", d[1]),
+        ZeroPage => format!("${:02X}", d[1]),
+        ZeroPageX => format!("${:02X},X", d[1]),
+        ZeroPageY => format!("${:02X},Y", d[1]),
+        Absolute => format!("${:04X}", w()),
+        AbsoluteX => format!("${:04X},X", w()),
+        AbsoluteY => format!("${:04X},Y", w()),
+        Indirect => format!("(${:04X})", w()),
+        IndirectX => format!("(${:02X},X)", d[1]),
+        IndirectY => format!("(${:02X}),Y", d[1]),
+        Relative => {
+            let target = address.wrapping_add(2).wrapping_add(d[1] as i8 as u16);
+            format!("${:04X}", target)
+        }
+    }
+}
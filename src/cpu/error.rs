@@ -0,0 +1,34 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuError {
+    Jammed { pc: u16, opcode: u8 },
+    Unstable { pc: u16, opcode: u8 },
+    BusFault { pc: u16, address: u16 },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::CpuError::*;
+        match *self {
+            Jammed { pc, opcode } => write!(f, "CPU jammed by opcode {:02X} at {:04X}", opcode, pc),
+            Unstable { pc, opcode } => {
+                write!(
+                    f,
+                    "Unstable opcode {:02X} at {:04X} is not emulated",
+                    opcode, pc
+                )
+            }
+            BusFault { pc, address } => {
+                write!(
+                    f,
+                    "Bus fault accessing {:04X} from instruction at {:04X}",
+                    address, pc
+                )
+            }
+        }
+    }
+}
+
+impl Error for CpuError {}
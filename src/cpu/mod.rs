@@ -1,7 +1,12 @@
+pub mod disasm;
+pub mod error;
 pub mod ops;
 pub mod reg;
+pub mod table;
+mod trace;
 
 use MMU;
+use cpu::error::CpuError;
 use cpu::ops::{AddressingMode, Operation};
 use cpu::reg::{Flag, Register, Registers};
 
@@ -9,102 +14,279 @@ const NMI_VECTOR: u16 = 0xFFFA;
 const RESET_VECTOR: u16 = 0xFFFC;
 const IRQ_VECTOR: u16 = 0xFFFE;
 
-pub struct CPU<'a> {
+// What a KIL opcode does: lock up like the 2A03, report an error, or act as a 1 byte NOP
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KillPolicy {
+    Halt,
+    Error,
+    Nop,
+}
+
+pub struct CPU<M: MMU> {
     pub reg: Registers,
-    pub bus: &'a mut MMU,
-    nmi: bool,
+    pub bus: M,
+    nmi: bool,        // NMI edge latched, cleared once serviced
+    nmi_line: bool,   // Bus NMI level seen on the last cycle, for edge detection
+    nmi_polled: bool, // NMI latch as it was on the second-to-last cycle
+    irq: bool,        // IRQ line level, held until released
+    irq_run: bool,    // IRQ line asserted and not masked on the last cycle
+    irq_polled: bool, // Same, one cycle earlier
+    pending: bool,    // Interrupt polled at the end of the last instruction
+    cycles: u8,
+    total_cycles: u64,
+    kill: KillPolicy,
+    jammed: bool,
 }
 
-impl<'a> CPU<'a> {
-    pub fn new(bus: &'a mut MMU) -> CPU {
+impl<M: MMU> CPU<M> {
+    pub fn new(bus: M) -> CPU<M> {
         CPU {
             reg: Registers::default(),
             bus,
             nmi: false,
+            nmi_line: false,
+            nmi_polled: false,
+            irq: false,
+            irq_run: false,
+            irq_polled: false,
+            pending: false,
+            cycles: 0,
+            total_cycles: 0,
+            kill: KillPolicy::Error,
+            jammed: false,
         }
     }
 
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn set_kill_policy(&mut self, p: KillPolicy) {
+        self.kill = p;
+    }
+
+    pub fn jammed(&self) -> bool {
+        self.jammed
+    }
+
+    pub fn into_bus(self) -> M {
+        self.bus
+    }
+
     fn cross(a: u16, b: u8) -> bool {
         ((a.wrapping_add(u16::from(b))) & 0xFF00) != (a & 0xFF00)
     }
 
     // #region Execution
     pub fn init(&mut self) {
+        self.jammed = false;
+
+        // Same 7 cycles as an interrupt, with the three pushes turned into reads
+        let pc = self.reg.read_pc();
+        self.read(pc);
+        self.read(pc);
+
+        for _ in 0..3 {
+            self.touch_stack();
+            let sp = self.reg.read(Register::SP);
+            self.reg.write(Register::SP, sp.wrapping_sub(1));
+        }
+
         let reset = self.read16(RESET_VECTOR);
         self.reg.write_pc(reset);
-        self.reg.write(Register::SP, 0xFD);
         self.reg.write(Register::P, 0x24);
     }
 
-    pub fn execute(&mut self) -> Result<(), String> {
+    pub fn execute(&mut self) -> Result<u8, CpuError> {
+        self.cycles = 0;
+
+        // Only a reset gets a jammed CPU going again
+        if self.jammed {
+            self.tick();
+            return Ok(self.cycles);
+        }
+
+        if self.pending {
+            let pc = self.reg.read_pc();
+            self.interrupt();
+            return self.check_fault(pc);
+        }
+
         let p = self.imm();
-        let ins: Operation = self.read(p).into();
+        let opcode = self.read(p);
+        let ins: Operation = opcode.into();
         use self::Operation::*;
 
         match ins {
-            Load(r, m) => Ok(self.load(r, m)),
-            Store(r, m) => Ok(self.store(r, m)),
-            Transfer(r1, r2) => Ok(self.transfer(r1, r2)),
-            Add(m) => Ok(self.add(m)),
-            Inc(Some(r), _) => Ok(self.inc_r(r)),
-            Inc(_, Some(m)) => Ok(self.inc_m(m)),
-            Dec(Some(r), _) => Ok(self.dec_r(r)),
-            Dec(_, Some(m)) => Ok(self.dec_m(m)),
-            Sub(m) => Ok(self.sub(m)),
-            And(m) => Ok(self.and(m)),
-            Asl(None) => Ok(self.asl_a()),
-            Asl(Some(m)) => Ok(self.asl(m)),
-            Bits(m) => Ok(self.bits(m)),
-            Xor(m) => Ok(self.xor(m)),
-            Lsr(None) => Ok(self.lsr_a()),
-            Lsr(Some(m)) => Ok(self.lsr(m)),
-            Or(m) => Ok(self.or(m)),
-            Rol(None) => Ok(self.rol_a()),
-            Rol(Some(m)) => Ok(self.rol(m)),
-            Ror(None) => Ok(self.ror_a()),
-            Ror(Some(m)) => Ok(self.ror(m)),
-            Branch(f, b) => Ok(self.branch(f, b)),
-            Jump(None) => Ok(self.jsr()),
-            Jump(Some(m)) => Ok(self.jump(m)),
-            Ret(true) => Ok(self.rts()),
-            Ret(false) => Ok(self.rti()),
-            Flag(f, b) => Ok(self.flag(f, b)),
-            Compare(r, m) => Ok(self.compare(r, m)),
-            Stack(r, b) => Ok(self.stack(r, b)),
-            Break => Ok(self.brk()),
-            Nop(m) => Ok(self.nop(m)),
-            Lax(m) => Ok(self.lax(m)),
-            Sax(m) => Ok(self.sax(m)),
-            Dcp(m) => Ok(self.dcp(m)),
-            Isb(m) => Ok(self.isb(m)),
-            Slo(m) => Ok(self.slo(m)),
-            Rla(m) => Ok(self.rla(m)),
-            Sre(m) => Ok(self.sre(m)),
-            Rra(m) => Ok(self.rra(m)),
-            Aac(m) => Ok(self.aac(m)),
-            Asr(m) => Ok(self.asr(m)),
-            Arr(m) => Ok(self.arr(m)),
-            Atx(m) => Ok(self.atx(m)),
-            Axs(m) => Ok(self.axs(m)),
-            Sa(r, m) => Ok(self.sa(r, m)),
-            _ => Err(format!("Bad Instruction {:02X}", p)),
+            Load(r, m) => self.load(r, m),
+            Store(r, m) => self.store(r, m),
+            Transfer(r1, r2) => self.transfer(r1, r2),
+            Add(m) => self.add(m),
+            Inc(Some(r), _) => self.inc_r(r),
+            Inc(_, Some(m)) => self.inc_m(m),
+            Dec(Some(r), _) => self.dec_r(r),
+            Dec(_, Some(m)) => self.dec_m(m),
+            Sub(m) => self.sub(m),
+            And(m) => self.and(m),
+            Asl(None) => self.asl_a(),
+            Asl(Some(m)) => self.asl(m),
+            Bits(m) => self.bits(m),
+            Xor(m) => self.xor(m),
+            Lsr(None) => self.lsr_a(),
+            Lsr(Some(m)) => self.lsr(m),
+            Or(m) => self.or(m),
+            Rol(None) => self.rol_a(),
+            Rol(Some(m)) => self.rol(m),
+            Ror(None) => self.ror_a(),
+            Ror(Some(m)) => self.ror(m),
+            Branch(f, b) => self.branch(f, b),
+            Jump(None) => self.jsr(),
+            Jump(Some(m)) => self.jump(m),
+            Ret(true) => self.rts(),
+            Ret(false) => self.rti(),
+            Flag(f, b) => self.flag(f, b),
+            Compare(r, m) => self.compare(r, m),
+            Stack(r, b) => self.stack(r, b),
+            Break => self.brk(),
+            Nop(m) => self.nop(m),
+            Lax(m) => self.lax(m),
+            Sax(m) => self.sax(m),
+            Dcp(m) => self.dcp(m),
+            Isb(m) => self.isb(m),
+            Slo(m) => self.slo(m),
+            Rla(m) => self.rla(m),
+            Sre(m) => self.sre(m),
+            Rra(m) => self.rra(m),
+            Aac(m) => self.aac(m),
+            Asr(m) => self.asr(m),
+            Arr(m) => self.arr(m),
+            Atx(m) => self.atx(m),
+            Axs(m) => self.axs(m),
+            Sa(r, m) => self.sa(r, m),
+            Kill => match self.kill {
+                KillPolicy::Halt => {
+                    self.jammed = true;
+                    return self.check_fault(p);
+                }
+                KillPolicy::Error => {
+                    self.reg.write_pc(p);
+                    self.check_fault(p)?;
+                    return Err(CpuError::Jammed { pc: p, opcode });
+                }
+                KillPolicy::Nop => self.nop(None),
+            },
+            _ => {
+                self.reg.write_pc(p);
+                self.check_fault(p)?;
+                return Err(CpuError::Unstable { pc: p, opcode });
+            }
+        }
+
+        // Interrupts are sampled at the end of the second-to-last cycle
+        self.pending = self.nmi_polled || self.irq_polled;
+        self.check_fault(p)
+    }
+
+    fn check_fault(&mut self, pc: u16) -> Result<u8, CpuError> {
+        match self.bus.fault() {
+            Some(address) => Err(CpuError::BusFault { pc, address }),
+            None => Ok(self.cycles),
+        }
+    }
+    // #endregion
+
+    // #region Interrupts
+    pub fn trigger_nmi(&mut self) {
+        self.nmi = true;
+    }
+
+    pub fn assert_irq(&mut self) {
+        self.irq = true;
+    }
+
+    pub fn release_irq(&mut self) {
+        self.irq = false;
+    }
+
+    // Runs at the end of every cycle. CLI, SEI and PLP change I after their last poll,
+    // which is what delays their effect by one instruction.
+    fn poll(&mut self) {
+        self.nmi_polled = self.nmi;
+
+        let line = self.bus.nmi();
+        if line && !self.nmi_line {
+            self.nmi = true;
         }
+        self.nmi_line = line;
+
+        self.irq_polled = self.irq_run;
+        self.irq_run = (self.irq || self.bus.irq()) && !self.reg.check_flag(Flag::Interrupt);
+    }
+
+    fn interrupt(&mut self) {
+        let pc = self.reg.read_pc();
+        self.read(pc);
+        self.read(pc);
+        self.push16(pc);
+
+        // Pushed with B clear, the vector is picked just before that push
+        let flags = self.reg.read(Register::P) & !0b0001_0000;
+        self.vector(flags);
+        self.pending = false;
+    }
+
+    // An NMI latched by now hijacks the BRK or IRQ sequence that is running
+    fn vector(&mut self, flags: u8) {
+        let vector = if self.nmi {
+            self.nmi = false;
+            NMI_VECTOR
+        } else {
+            IRQ_VECTOR
+        };
+
+        self.push(flags);
+        self.reg.update_flag(Flag::Interrupt, true);
+
+        let val = self.read16(vector);
+        self.reg.write_pc(val);
     }
     // #endregion
 
     // #region Read / Write
-    fn read(&mut self, a: u16) -> u8 {
+    fn tick(&mut self) {
+        self.cycles += 1;
+        self.total_cycles += 1;
         self.bus.cycle();
-        self.bus.read(a)
+    }
+
+    fn read(&mut self, a: u16) -> u8 {
+        self.tick();
+        let v = self.bus.read(a);
+        self.poll();
+        v
     }
 
     fn write(&mut self, a: u16, v: u8) {
-        self.bus.cycle();
-        self.bus.write(a, v)
+        self.tick();
+        self.bus.write(a, v);
+        self.poll();
     }
 
     fn read16(&mut self, a: u16) -> u16 {
-        u16::from(self.read(a)) | (u16::from(self.read(a + 1)) << 8)
+        u16::from(self.read(a)) | (u16::from(self.read(a.wrapping_add(1))) << 8)
+    }
+
+    // Read-modify-write instructions write the unmodified value back before the result
+    fn read_modify(&mut self, a: u16) -> u8 {
+        let v = self.read(a);
+        self.write(a, v);
+        v
+    }
+
+    // Implied and stack instructions read the byte after the opcode and discard it
+    fn idle(&mut self) {
+        let pc = self.reg.read_pc();
+        self.read(pc);
     }
 
     fn write16(&mut self, a: u16, v: u16) {
@@ -131,6 +313,12 @@ impl<'a> CPU<'a> {
         self.read(u16::from(sp) + 0x100)
     }
 
+    // Dummy read at the stack pointer while it gets incremented
+    fn touch_stack(&mut self) {
+        let sp = self.reg.read(Register::SP);
+        self.read(u16::from(sp) + 0x100);
+    }
+
     fn pop16(&mut self) -> u16 {
         u16::from(self.pop()) | (u16::from(self.pop()) << 8)
     }
@@ -157,12 +345,19 @@ impl<'a> CPU<'a> {
     fn abi(&mut self, extra: bool, r: Register) -> u16 {
         let a = self.abs();
         let reg = self.reg.read(r);
+        self.index(a, reg, extra)
+    }
 
-        if extra && CPU::cross(a, reg) {
-            self.bus.cycle();
+    // Adds the index to the low byte first, reading from the un-carried address when the
+    // high byte still needs fixing, or always for writes and read-modify-writes
+    fn index(&mut self, a: u16, i: u8, extra: bool) -> u16 {
+        let res = a.wrapping_add(u16::from(i));
+
+        if !extra || Self::cross(a, i) {
+            self.read((a & 0xFF00) | (res & 0xFF));
         }
 
-        a.wrapping_add(u16::from(reg))
+        res
     }
 
     fn zp(&mut self) -> u16 {
@@ -172,15 +367,16 @@ impl<'a> CPU<'a> {
 
     fn zpi(&mut self, r: Register) -> u16 {
         let a = self.zp();
-        self.bus.cycle();
+        self.read(a);
         (a + u16::from(self.reg.read(r))) & 0xFF
     }
 
     fn izx(&mut self) -> u16 {
         let imm = self.imm();
-        let res = self.read(imm).wrapping_add(self.reg.read(Register::X));
+        let zero = self.read(imm);
+        self.read(u16::from(zero));
 
-        self.bus.cycle();
+        let res = zero.wrapping_add(self.reg.read(Register::X));
 
         if res == 0xFF {
             u16::from(self.read(0xFF)) | (u16::from(self.read(0x00)) << 8)
@@ -194,19 +390,13 @@ impl<'a> CPU<'a> {
         let zero = self.read(imm);
         let y = self.reg.read(Register::Y);
 
-        self.bus.cycle();
-
         let addr = if zero == 0xFF {
             u16::from(self.read(0xFF)) | (u16::from(self.read(0x00)) << 8)
         } else {
             self.read16(u16::from(zero))
         };
 
-        if extra && CPU::cross(addr.wrapping_sub(u16::from(y)), y) {
-            self.bus.cycle();
-        }
-
-        addr.wrapping_add(u16::from(y))
+        self.index(addr, y, extra)
     }
 
     fn ind(&mut self) -> u16 {
@@ -253,6 +443,7 @@ impl<'a> CPU<'a> {
     fn transfer(&mut self, from: Register, to: Register) {
         let s = self.reg.read(from);
         self.reg.write(to, s);
+        self.idle();
     }
 
     fn add(&mut self, m: AddressingMode) {
@@ -274,8 +465,7 @@ impl<'a> CPU<'a> {
 
     fn dec_m(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr).wrapping_sub(1);
-        self.bus.cycle();
+        let value = self.read_modify(addr).wrapping_sub(1);
 
         self.reg.update_zn(value);
         self.write(addr, value);
@@ -284,13 +474,12 @@ impl<'a> CPU<'a> {
     fn dec_r(&mut self, r: Register) {
         let v = self.reg.read(r).wrapping_sub(1);
         self.reg.write(r, v);
-        self.bus.cycle();
+        self.idle();
     }
 
     fn inc_m(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr).wrapping_add(1);
-        self.bus.cycle();
+        let value = self.read_modify(addr).wrapping_add(1);
 
         self.reg.update_zn(value);
         self.write(addr, value);
@@ -299,7 +488,7 @@ impl<'a> CPU<'a> {
     fn inc_r(&mut self, r: Register) {
         let v = self.reg.read(r).wrapping_add(1);
         self.reg.write(r, v);
-        self.bus.cycle();
+        self.idle();
     }
 
     fn sub(&mut self, m: AddressingMode) {
@@ -331,15 +520,14 @@ impl<'a> CPU<'a> {
 
         self.reg.update_flag(Flag::Carry, value & 0x80 == 0x80);
         self.reg.write(Register::A, value << 1);
-        self.bus.cycle();
+        self.idle();
     }
 
     fn asl(&mut self, r: AddressingMode) {
         let addr = self.resolve_addr(r);
-        let value = self.read(addr);
+        let value = self.read_modify(addr);
 
         self.reg.update_flag(Flag::Carry, value & 0x80 == 0x80);
-        self.bus.cycle();
 
         self.reg.update_zn(value << 1);
         self.write(addr, value << 1);
@@ -367,15 +555,14 @@ impl<'a> CPU<'a> {
 
         self.reg.update_flag(Flag::Carry, value & 0x01 == 0x01);
         self.reg.write(Register::A, value >> 1);
-        self.bus.cycle();
+        self.idle();
     }
 
     fn lsr(&mut self, r: AddressingMode) {
         let addr = self.resolve_addr(r);
-        let value = self.read(addr);
+        let value = self.read_modify(addr);
 
         self.reg.update_flag(Flag::Carry, value & 0x01 == 0x01);
-        self.bus.cycle();
 
         self.reg.update_zn(value >> 1);
         self.write(addr, value >> 1);
@@ -389,8 +576,8 @@ impl<'a> CPU<'a> {
     }
 
     fn flag(&mut self, f: Flag, s: bool) {
+        self.idle();
         self.reg.update_flag(f, s);
-        self.bus.cycle();
     }
 
     fn compare(&mut self, r: Register, m: AddressingMode) {
@@ -407,12 +594,16 @@ impl<'a> CPU<'a> {
     }
 
     fn jsr(&mut self) {
-        let t = self.reg.read_pc().wrapping_add(1);
-        self.bus.cycle();
-        self.push16(t);
-        let addr = self.imm16();
-        let value = self.read16(addr);
-        self.reg.write_pc(value);
+        let imm = self.imm();
+        let lo = self.read(imm);
+
+        self.touch_stack();
+
+        let pc = self.reg.read_pc();
+        self.push16(pc);
+
+        let hi = self.read(pc);
+        self.reg.write_pc(u16::from(lo) | (u16::from(hi) << 8));
     }
 
     fn jump(&mut self, m: AddressingMode) {
@@ -422,7 +613,7 @@ impl<'a> CPU<'a> {
 
     fn stack(&mut self, r: Register, push: bool) {
         if push {
-            self.bus.cycle();
+            self.idle();
 
             let value = match r {
                 Register::P => self.reg.read(Register::P) | 0b0001_0000,
@@ -431,8 +622,8 @@ impl<'a> CPU<'a> {
 
             self.push(value);
         } else {
-            self.bus.cycle();
-            self.bus.cycle();
+            self.idle();
+            self.touch_stack();
             let value = self.pop();
             self.reg.write(r, value);
         }
@@ -445,29 +636,25 @@ impl<'a> CPU<'a> {
     }
 
     fn rts(&mut self) {
-        self.bus.cycle();
-        self.bus.cycle();
-        let addr = self.pop16().wrapping_add(1);
-        self.reg.write_pc(addr);
-        self.bus.cycle();
+        self.idle();
+        self.touch_stack();
+        let addr = self.pop16();
+        self.read(addr);
+        self.reg.write_pc(addr.wrapping_add(1));
     }
 
     fn brk(&mut self) {
-        let addr = self.reg.read_pc().wrapping_add(1);
+        let imm = self.imm();
+        self.read(imm);
+
+        let addr = self.reg.read_pc();
         self.push16(addr);
 
         let flags = self.reg.read(Register::P) | 0b0001_0000;
+        self.vector(flags);
 
-        self.push(flags);
-        self.reg.update_flag(Flag::Interrupt, true);
-
-        let val = if self.nmi {
-            self.read16(NMI_VECTOR)
-        } else {
-            self.read16(IRQ_VECTOR)
-        };
-
-        self.reg.write_pc(val);
+        // No NMI right after a BRK, even one that was hijacked
+        self.nmi_polled = false;
     }
 
     fn branch(&mut self, cond: Flag, when: bool) {
@@ -475,24 +662,36 @@ impl<'a> CPU<'a> {
         let value = self.read(addr) as i8;
 
         if self.reg.check_flag(cond) == when {
-            self.bus.cycle();
+            // A taken branch doesn't poll on its last cycle unless it crosses a page
+            let nmi_late = self.nmi && !self.nmi_polled;
+            if self.irq_run && !self.irq_polled {
+                self.irq_run = false;
+            }
+
+            self.idle();
+            if nmi_late {
+                self.nmi_polled = false;
+            }
+
             let pc = self.reg.read_pc();
+            let res = pc.wrapping_add(value as u16);
 
-            if CPU::cross(pc, value as u8) {
-                self.bus.cycle();
+            if res & 0xFF00 != pc & 0xFF00 {
+                self.read((pc & 0xFF00) | (res & 0xFF));
             }
 
-            let res = pc as i16 + i16::from(value);
-            self.reg.write_pc(res as u16);
+            self.reg.write_pc(res);
         }
     }
 
     fn nop(&mut self, m: Option<AddressingMode>) {
-        if let Some(m) = m {
-            let addr = self.resolve_addr(m);
-            self.read(addr);
+        match m {
+            Some(m) => {
+                let addr = self.resolve_addr(m);
+                self.read(addr);
+            }
+            None => self.idle(),
         }
-        self.bus.cycle();
     }
 
     fn rol_a(&mut self) {
@@ -505,7 +704,7 @@ impl<'a> CPU<'a> {
         let value = self.reg.read(Register::A);
         self.reg.update_flag(Flag::Carry, value & 0x80 == 0x80);
         self.reg.write(Register::A, (value << 1) | c);
-        self.bus.cycle();
+        self.idle();
     }
 
     fn rol(&mut self, m: AddressingMode) {
@@ -516,10 +715,9 @@ impl<'a> CPU<'a> {
         };
 
         let addr = self.resolve_addr(m);
-        let value = self.read(addr);
+        let value = self.read_modify(addr);
 
         self.reg.update_flag(Flag::Carry, value & 0x80 == 0x80);
-        self.bus.cycle();
 
         self.reg.update_zn((value << 1) | c);
         self.write(addr, (value << 1) | c);
@@ -535,7 +733,7 @@ impl<'a> CPU<'a> {
         let value = self.reg.read(Register::A);
         self.reg.update_flag(Flag::Carry, value & 0x01 == 0x01);
         self.reg.write(Register::A, c | (value >> 1));
-        self.bus.cycle();
+        self.idle();
     }
 
     fn ror(&mut self, m: AddressingMode) {
@@ -546,10 +744,9 @@ impl<'a> CPU<'a> {
         };
 
         let addr = self.resolve_addr(m);
-        let value = self.read(addr);
+        let value = self.read_modify(addr);
 
         self.reg.update_flag(Flag::Carry, value & 0x01 == 0x01);
-        self.bus.cycle();
 
         self.reg.update_zn(c | (value >> 1));
         self.write(addr, c | (value >> 1));
@@ -575,9 +772,7 @@ impl<'a> CPU<'a> {
 
     fn dcp(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr).wrapping_sub(1);
-
-        self.bus.cycle();
+        let value = self.read_modify(addr).wrapping_sub(1);
 
         let reg = self.reg.read(Register::A);
         self.reg.update_flag(Flag::Carry, reg >= value);
@@ -592,8 +787,7 @@ impl<'a> CPU<'a> {
 
     fn isb(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr).wrapping_add(1);
-        self.bus.cycle();
+        let value = self.read_modify(addr).wrapping_add(1);
 
         let a = self.reg.read(Register::A);
         let b = value ^ 0xFF;
@@ -612,8 +806,7 @@ impl<'a> CPU<'a> {
 
     fn slo(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr);
-        self.bus.cycle();
+        let value = self.read_modify(addr);
 
         self.reg.update_flag(Flag::Carry, value & 0x80 == 0x80);
         let a = self.reg.read(Register::A);
@@ -625,9 +818,7 @@ impl<'a> CPU<'a> {
 
     fn rla(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr);
-
-        self.bus.cycle();
+        let value = self.read_modify(addr);
 
         let c = if self.reg.check_flag(Flag::Carry) {
             1
@@ -646,9 +837,7 @@ impl<'a> CPU<'a> {
 
     fn sre(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr);
-
-        self.bus.cycle();
+        let value = self.read_modify(addr);
 
         self.reg.update_flag(Flag::Carry, value & 0x01 == 0x01);
 
@@ -662,9 +851,7 @@ impl<'a> CPU<'a> {
 
     fn rra(&mut self, m: AddressingMode) {
         let addr = self.resolve_addr(m);
-        let value = self.read(addr);
-
-        self.bus.cycle();
+        let value = self.read_modify(addr);
 
         let c = if self.reg.check_flag(Flag::Carry) {
             0x80
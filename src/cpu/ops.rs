@@ -1,6 +1,6 @@
 use cpu::reg::{Flag, Register};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AddressingMode {
     Immediate,
     Absolute,
@@ -14,7 +14,7 @@ pub enum AddressingMode {
     IndirectY(bool),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Operation {
     // Storage
     Load(Register, AddressingMode),
@@ -71,8 +71,43 @@ pub enum Operation {
     Axs(AddressingMode),
     Sa(Register, AddressingMode),
 
-    // Unknown Opcode
+    // Jam / Unknown Opcode
     Kill,
+    Unstable,
+}
+
+impl Operation {
+    pub fn mode(self) -> Option<AddressingMode> {
+        use self::Operation::*;
+
+        match self {
+            Load(_, m)
+            | Store(_, m)
+            | Add(m)
+            | Sub(m)
+            | And(m)
+            | Bits(m)
+            | Xor(m)
+            | Or(m)
+            | Compare(_, m)
+            | Lax(m)
+            | Sax(m)
+            | Dcp(m)
+            | Isb(m)
+            | Slo(m)
+            | Rla(m)
+            | Sre(m)
+            | Rra(m)
+            | Aac(m)
+            | Asr(m)
+            | Arr(m)
+            | Atx(m)
+            | Axs(m)
+            | Sa(_, m) => Some(m),
+            Inc(_, m) | Dec(_, m) | Asl(m) | Lsr(m) | Rol(m) | Ror(m) | Nop(m) | Jump(m) => m,
+            _ => None,
+        }
+    }
 }
 
 impl From<u8> for Operation {
@@ -136,7 +171,7 @@ impl From<u8> for Operation {
             0x58 => Flag(Interrupt, false),
             0x59 => Xor(AbsoluteY(true)),
             0x5D => Xor(AbsoluteX(true)),
-            0x5E => Lsr(Some(AbsoluteX(true))),
+            0x5E => Lsr(Some(AbsoluteX(false))),
             0x60 => Ret(true),
             0x61 => Add(IndirectX),
             0x65 => Add(ZeroPage),
@@ -238,8 +273,7 @@ impl From<u8> for Operation {
             0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => Nop(Some(ZeroPageX)),
             0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => Nop(Some(Immediate)),
             0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => Nop(Some(AbsoluteX(true))),
-            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA | 0xEA | 0x02 | 0x12 | 0x22 | 0x32 | 0x42
-            | 0x52 | 0x62 | 0x72 | 0x92 | 0xB2 | 0xD2 | 0xF2 => Nop(None),
+            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA | 0xEA => Nop(None),
             0xA7 => Lax(ZeroPage),
             0xB7 => Lax(ZeroPageY),
             0xAF => Lax(Absolute),
@@ -299,7 +333,10 @@ impl From<u8> for Operation {
             0xCB => Axs(Immediate),
             0x9C => Sa(Y, AbsoluteX(false)),
             0x9E => Sa(X, AbsoluteY(false)),
-            _ => Kill,
+            0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xB2 | 0xD2 | 0xF2 => {
+                Kill
+            }
+            _ => Unstable,
         }
     }
 }
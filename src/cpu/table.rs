@@ -0,0 +1,340 @@
+// Static description of every opcode, for tools that need to reason about
+// instructions without running them (disassembler, profiler, assembler).
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+    Relative,
+}
+
+impl Mode {
+    pub const fn length(self) -> u8 {
+        use self::Mode::*;
+        match self {
+            Implied | Accumulator => 1,
+            Absolute | AbsoluteX | AbsoluteY | Indirect => 3,
+            _ => 2,
+        }
+    }
+}
+
+// How the instruction touches its operand in memory, Implied when it has none
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Implied,
+    Read,
+    Write,
+    ReadModifyWrite,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub mode: Mode,
+    pub length: u8,
+    pub cycles: u8,
+    pub page_cross: bool, // One extra cycle when indexing crosses a page
+    pub access: Access,
+    pub official: bool,
+}
+
+macro_rules! op {
+    ($m:expr, $mode:ident, $c:expr, $x:expr, $a:ident, $o:expr) => {
+        Opcode {
+            mnemonic: $m,
+            mode: Mode::$mode,
+            length: Mode::$mode.length(),
+            cycles: $c,
+            page_cross: $x,
+            access: Access::$a,
+            official: $o,
+        }
+    };
+}
+
+// Taken branches add one cycle, plus another when the target is on a different page
+pub static OPCODES: [Opcode; 0x100] = [
+    // 0x00
+    op!("BRK", Implied, 7, false, Implied, true),
+    op!("ORA", IndirectX, 6, false, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("SLO", IndirectX, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPage, 3, false, Read, false),
+    op!("ORA", ZeroPage, 3, false, Read, true),
+    op!("ASL", ZeroPage, 5, false, ReadModifyWrite, true),
+    op!("SLO", ZeroPage, 5, false, ReadModifyWrite, false),
+    op!("PHP", Implied, 3, false, Implied, true),
+    op!("ORA", Immediate, 2, false, Read, true),
+    op!("ASL", Accumulator, 2, false, Implied, true),
+    op!("AAC", Immediate, 2, false, Read, false),
+    op!("NOP", Absolute, 4, false, Read, false),
+    op!("ORA", Absolute, 4, false, Read, true),
+    op!("ASL", Absolute, 6, false, ReadModifyWrite, true),
+    op!("SLO", Absolute, 6, false, ReadModifyWrite, false),
+    // 0x10
+    op!("BPL", Relative, 2, false, Implied, true),
+    op!("ORA", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("SLO", IndirectY, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPageX, 4, false, Read, false),
+    op!("ORA", ZeroPageX, 4, false, Read, true),
+    op!("ASL", ZeroPageX, 6, false, ReadModifyWrite, true),
+    op!("SLO", ZeroPageX, 6, false, ReadModifyWrite, false),
+    op!("CLC", Implied, 2, false, Implied, true),
+    op!("ORA", AbsoluteY, 4, true, Read, true),
+    op!("NOP", Implied, 2, false, Implied, false),
+    op!("SLO", AbsoluteY, 7, false, ReadModifyWrite, false),
+    op!("NOP", AbsoluteX, 4, true, Read, false),
+    op!("ORA", AbsoluteX, 4, true, Read, true),
+    op!("ASL", AbsoluteX, 7, false, ReadModifyWrite, true),
+    op!("SLO", AbsoluteX, 7, false, ReadModifyWrite, false),
+    // 0x20
+    op!("JSR", Absolute, 6, false, Implied, true),
+    op!("AND", IndirectX, 6, false, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("RLA", IndirectX, 8, false, ReadModifyWrite, false),
+    op!("BIT", ZeroPage, 3, false, Read, true),
+    op!("AND", ZeroPage, 3, false, Read, true),
+    op!("ROL", ZeroPage, 5, false, ReadModifyWrite, true),
+    op!("RLA", ZeroPage, 5, false, ReadModifyWrite, false),
+    op!("PLP", Implied, 4, false, Implied, true),
+    op!("AND", Immediate, 2, false, Read, true),
+    op!("ROL", Accumulator, 2, false, Implied, true),
+    op!("AAC", Immediate, 2, false, Read, false),
+    op!("BIT", Absolute, 4, false, Read, true),
+    op!("AND", Absolute, 4, false, Read, true),
+    op!("ROL", Absolute, 6, false, ReadModifyWrite, true),
+    op!("RLA", Absolute, 6, false, ReadModifyWrite, false),
+    // 0x30
+    op!("BMI", Relative, 2, false, Implied, true),
+    op!("AND", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("RLA", IndirectY, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPageX, 4, false, Read, false),
+    op!("AND", ZeroPageX, 4, false, Read, true),
+    op!("ROL", ZeroPageX, 6, false, ReadModifyWrite, true),
+    op!("RLA", ZeroPageX, 6, false, ReadModifyWrite, false),
+    op!("SEC", Implied, 2, false, Implied, true),
+    op!("AND", AbsoluteY, 4, true, Read, true),
+    op!("NOP", Implied, 2, false, Implied, false),
+    op!("RLA", AbsoluteY, 7, false, ReadModifyWrite, false),
+    op!("NOP", AbsoluteX, 4, true, Read, false),
+    op!("AND", AbsoluteX, 4, true, Read, true),
+    op!("ROL", AbsoluteX, 7, false, ReadModifyWrite, true),
+    op!("RLA", AbsoluteX, 7, false, ReadModifyWrite, false),
+    // 0x40
+    op!("RTI", Implied, 6, false, Implied, true),
+    op!("EOR", IndirectX, 6, false, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("SRE", IndirectX, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPage, 3, false, Read, false),
+    op!("EOR", ZeroPage, 3, false, Read, true),
+    op!("LSR", ZeroPage, 5, false, ReadModifyWrite, true),
+    op!("SRE", ZeroPage, 5, false, ReadModifyWrite, false),
+    op!("PHA", Implied, 3, false, Implied, true),
+    op!("EOR", Immediate, 2, false, Read, true),
+    op!("LSR", Accumulator, 2, false, Implied, true),
+    op!("ASR", Immediate, 2, false, Read, false),
+    op!("JMP", Absolute, 3, false, Implied, true),
+    op!("EOR", Absolute, 4, false, Read, true),
+    op!("LSR", Absolute, 6, false, ReadModifyWrite, true),
+    op!("SRE", Absolute, 6, false, ReadModifyWrite, false),
+    // 0x50
+    op!("BVC", Relative, 2, false, Implied, true),
+    op!("EOR", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("SRE", IndirectY, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPageX, 4, false, Read, false),
+    op!("EOR", ZeroPageX, 4, false, Read, true),
+    op!("LSR", ZeroPageX, 6, false, ReadModifyWrite, true),
+    op!("SRE", ZeroPageX, 6, false, ReadModifyWrite, false),
+    op!("CLI", Implied, 2, false, Implied, true),
+    op!("EOR", AbsoluteY, 4, true, Read, true),
+    op!("NOP", Implied, 2, false, Implied, false),
+    op!("SRE", AbsoluteY, 7, false, ReadModifyWrite, false),
+    op!("NOP", AbsoluteX, 4, true, Read, false),
+    op!("EOR", AbsoluteX, 4, true, Read, true),
+    op!("LSR", AbsoluteX, 7, false, ReadModifyWrite, true),
+    op!("SRE", AbsoluteX, 7, false, ReadModifyWrite, false),
+    // 0x60
+    op!("RTS", Implied, 6, false, Implied, true),
+    op!("ADC", IndirectX, 6, false, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("RRA", IndirectX, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPage, 3, false, Read, false),
+    op!("ADC", ZeroPage, 3, false, Read, true),
+    op!("ROR", ZeroPage, 5, false, ReadModifyWrite, true),
+    op!("RRA", ZeroPage, 5, false, ReadModifyWrite, false),
+    op!("PLA", Implied, 4, false, Implied, true),
+    op!("ADC", Immediate, 2, false, Read, true),
+    op!("ROR", Accumulator, 2, false, Implied, true),
+    op!("ARR", Immediate, 2, false, Read, false),
+    op!("JMP", Indirect, 5, false, Implied, true),
+    op!("ADC", Absolute, 4, false, Read, true),
+    op!("ROR", Absolute, 6, false, ReadModifyWrite, true),
+    op!("RRA", Absolute, 6, false, ReadModifyWrite, false),
+    // 0x70
+    op!("BVS", Relative, 2, false, Implied, true),
+    op!("ADC", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("RRA", IndirectY, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPageX, 4, false, Read, false),
+    op!("ADC", ZeroPageX, 4, false, Read, true),
+    op!("ROR", ZeroPageX, 6, false, ReadModifyWrite, true),
+    op!("RRA", ZeroPageX, 6, false, ReadModifyWrite, false),
+    op!("SEI", Implied, 2, false, Implied, true),
+    op!("ADC", AbsoluteY, 4, true, Read, true),
+    op!("NOP", Implied, 2, false, Implied, false),
+    op!("RRA", AbsoluteY, 7, false, ReadModifyWrite, false),
+    op!("NOP", AbsoluteX, 4, true, Read, false),
+    op!("ADC", AbsoluteX, 4, true, Read, true),
+    op!("ROR", AbsoluteX, 7, false, ReadModifyWrite, true),
+    op!("RRA", AbsoluteX, 7, false, ReadModifyWrite, false),
+    // 0x80
+    op!("NOP", Immediate, 2, false, Read, false),
+    op!("STA", IndirectX, 6, false, Write, true),
+    op!("NOP", Immediate, 2, false, Read, false),
+    op!("SAX", IndirectX, 6, false, Write, false),
+    op!("STY", ZeroPage, 3, false, Write, true),
+    op!("STA", ZeroPage, 3, false, Write, true),
+    op!("STX", ZeroPage, 3, false, Write, true),
+    op!("SAX", ZeroPage, 3, false, Write, false),
+    op!("DEY", Implied, 2, false, Implied, true),
+    op!("NOP", Immediate, 2, false, Read, false),
+    op!("TXA", Implied, 2, false, Implied, true),
+    op!("XAA", Immediate, 2, false, Read, false),
+    op!("STY", Absolute, 4, false, Write, true),
+    op!("STA", Absolute, 4, false, Write, true),
+    op!("STX", Absolute, 4, false, Write, true),
+    op!("SAX", Absolute, 4, false, Write, false),
+    // 0x90
+    op!("BCC", Relative, 2, false, Implied, true),
+    op!("STA", IndirectY, 6, false, Write, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("AXA", IndirectY, 6, false, Write, false),
+    op!("STY", ZeroPageX, 4, false, Write, true),
+    op!("STA", ZeroPageX, 4, false, Write, true),
+    op!("STX", ZeroPageY, 4, false, Write, true),
+    op!("SAX", ZeroPageY, 4, false, Write, false),
+    op!("TYA", Implied, 2, false, Implied, true),
+    op!("STA", AbsoluteY, 5, false, Write, true),
+    op!("TXS", Implied, 2, false, Implied, true),
+    op!("XAS", AbsoluteY, 5, false, Write, false),
+    op!("SYA", AbsoluteX, 5, false, Write, false),
+    op!("STA", AbsoluteX, 5, false, Write, true),
+    op!("SXA", AbsoluteY, 5, false, Write, false),
+    op!("AXA", AbsoluteY, 5, false, Write, false),
+    // 0xA0
+    op!("LDY", Immediate, 2, false, Read, true),
+    op!("LDA", IndirectX, 6, false, Read, true),
+    op!("LDX", Immediate, 2, false, Read, true),
+    op!("LAX", IndirectX, 6, false, Read, false),
+    op!("LDY", ZeroPage, 3, false, Read, true),
+    op!("LDA", ZeroPage, 3, false, Read, true),
+    op!("LDX", ZeroPage, 3, false, Read, true),
+    op!("LAX", ZeroPage, 3, false, Read, false),
+    op!("TAY", Implied, 2, false, Implied, true),
+    op!("LDA", Immediate, 2, false, Read, true),
+    op!("TAX", Implied, 2, false, Implied, true),
+    op!("ATX", Immediate, 2, false, Read, false),
+    op!("LDY", Absolute, 4, false, Read, true),
+    op!("LDA", Absolute, 4, false, Read, true),
+    op!("LDX", Absolute, 4, false, Read, true),
+    op!("LAX", Absolute, 4, false, Read, false),
+    // 0xB0
+    op!("BCS", Relative, 2, false, Implied, true),
+    op!("LDA", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("LAX", IndirectY, 5, true, Read, false),
+    op!("LDY", ZeroPageX, 4, false, Read, true),
+    op!("LDA", ZeroPageX, 4, false, Read, true),
+    op!("LDX", ZeroPageY, 4, false, Read, true),
+    op!("LAX", ZeroPageY, 4, false, Read, false),
+    op!("CLV", Implied, 2, false, Implied, true),
+    op!("LDA", AbsoluteY, 4, true, Read, true),
+    op!("TSX", Implied, 2, false, Implied, true),
+    op!("LAR", AbsoluteY, 4, true, Read, false),
+    op!("LDY", AbsoluteX, 4, true, Read, true),
+    op!("LDA", AbsoluteX, 4, true, Read, true),
+    op!("LDX", AbsoluteY, 4, true, Read, true),
+    op!("LAX", AbsoluteY, 4, true, Read, false),
+    // 0xC0
+    op!("CPY", Immediate, 2, false, Read, true),
+    op!("CMP", IndirectX, 6, false, Read, true),
+    op!("NOP", Immediate, 2, false, Read, false),
+    op!("DCP", IndirectX, 8, false, ReadModifyWrite, false),
+    op!("CPY", ZeroPage, 3, false, Read, true),
+    op!("CMP", ZeroPage, 3, false, Read, true),
+    op!("DEC", ZeroPage, 5, false, ReadModifyWrite, true),
+    op!("DCP", ZeroPage, 5, false, ReadModifyWrite, false),
+    op!("INY", Implied, 2, false, Implied, true),
+    op!("CMP", Immediate, 2, false, Read, true),
+    op!("DEX", Implied, 2, false, Implied, true),
+    op!("AXS", Immediate, 2, false, Read, false),
+    op!("CPY", Absolute, 4, false, Read, true),
+    op!("CMP", Absolute, 4, false, Read, true),
+    op!("DEC", Absolute, 6, false, ReadModifyWrite, true),
+    op!("DCP", Absolute, 6, false, ReadModifyWrite, false),
+    // 0xD0
+    op!("BNE", Relative, 2, false, Implied, true),
+    op!("CMP", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("DCP", IndirectY, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPageX, 4, false, Read, false),
+    op!("CMP", ZeroPageX, 4, false, Read, true),
+    op!("DEC", ZeroPageX, 6, false, ReadModifyWrite, true),
+    op!("DCP", ZeroPageX, 6, false, ReadModifyWrite, false),
+    op!("CLD", Implied, 2, false, Implied, true),
+    op!("CMP", AbsoluteY, 4, true, Read, true),
+    op!("NOP", Implied, 2, false, Implied, false),
+    op!("DCP", AbsoluteY, 7, false, ReadModifyWrite, false),
+    op!("NOP", AbsoluteX, 4, true, Read, false),
+    op!("CMP", AbsoluteX, 4, true, Read, true),
+    op!("DEC", AbsoluteX, 7, false, ReadModifyWrite, true),
+    op!("DCP", AbsoluteX, 7, false, ReadModifyWrite, false),
+    // 0xE0
+    op!("CPX", Immediate, 2, false, Read, true),
+    op!("SBC", IndirectX, 6, false, Read, true),
+    op!("NOP", Immediate, 2, false, Read, false),
+    op!("ISB", IndirectX, 8, false, ReadModifyWrite, false),
+    op!("CPX", ZeroPage, 3, false, Read, true),
+    op!("SBC", ZeroPage, 3, false, Read, true),
+    op!("INC", ZeroPage, 5, false, ReadModifyWrite, true),
+    op!("ISB", ZeroPage, 5, false, ReadModifyWrite, false),
+    op!("INX", Implied, 2, false, Implied, true),
+    op!("SBC", Immediate, 2, false, Read, true),
+    op!("NOP", Implied, 2, false, Implied, true),
+    op!("SBC", Immediate, 2, false, Read, false),
+    op!("CPX", Absolute, 4, false, Read, true),
+    op!("SBC", Absolute, 4, false, Read, true),
+    op!("INC", Absolute, 6, false, ReadModifyWrite, true),
+    op!("ISB", Absolute, 6, false, ReadModifyWrite, false),
+    // 0xF0
+    op!("BEQ", Relative, 2, false, Implied, true),
+    op!("SBC", IndirectY, 5, true, Read, true),
+    op!("KIL", Implied, 2, false, Implied, false),
+    op!("ISB", IndirectY, 8, false, ReadModifyWrite, false),
+    op!("NOP", ZeroPageX, 4, false, Read, false),
+    op!("SBC", ZeroPageX, 4, false, Read, true),
+    op!("INC", ZeroPageX, 6, false, ReadModifyWrite, true),
+    op!("ISB", ZeroPageX, 6, false, ReadModifyWrite, false),
+    op!("SED", Implied, 2, false, Implied, true),
+    op!("SBC", AbsoluteY, 4, true, Read, true),
+    op!("NOP", Implied, 2, false, Implied, false),
+    op!("ISB", AbsoluteY, 7, false, ReadModifyWrite, false),
+    op!("NOP", AbsoluteX, 4, true, Read, false),
+    op!("SBC", AbsoluteX, 4, true, Read, true),
+    op!("INC", AbsoluteX, 7, false, ReadModifyWrite, true),
+    op!("ISB", AbsoluteX, 7, false, ReadModifyWrite, false),
+];
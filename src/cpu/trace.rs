@@ -0,0 +1,109 @@
+use cpu::disasm::{disassemble_at, Instruction};
+use cpu::ops::{AddressingMode, Operation};
+use cpu::reg::Register;
+use cpu::CPU;
+use MMU;
+
+// PPU dots in an even frame. Odd frames skip the last dot of the pre-render line.
+const FRAME_DOTS: u64 = 341 * 262;
+
+// Scanline and dot the PPU is at after `cycles` CPU cycles, three dots each
+fn ppu_position(cycles: u64) -> (u64, u64) {
+    let dot = (cycles * 3) % (FRAME_DOTS * 2 - 1);
+    let dot = if dot < FRAME_DOTS {
+        dot
+    } else {
+        dot - FRAME_DOTS
+    };
+    (dot / 341, dot % 341)
+}
+
+impl<M: MMU> CPU<M> {
+    // Nintendulator style line for the instruction at PC, as found in nestest.log
+    pub fn trace(&self) -> String {
+        use self::Register::*;
+
+        let ins = disassemble_at(&self.bus, self.reg.read_pc());
+
+        let bytes = ins
+            .bytes
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let marker = if ins.official { ' ' } else { '*' };
+        let text = format!("{} {}{}", ins.mnemonic, ins.operand, self.annotate(&ins));
+        let (scanline, dot) = ppu_position(self.total_cycles);
+
+        format!(
+            "{:04X}  {:<9}{}{:<32}A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} PPU:{:>3},{:>3} CYC:{}",
+            ins.address,
+            bytes,
+            marker,
+            text.trim_end(),
+            self.reg.read(A),
+            self.reg.read(X),
+            self.reg.read(Y),
+            self.reg.read(P),
+            self.reg.read(SP),
+            scanline,
+            dot,
+            self.total_cycles
+        )
+    }
+
+    // The I/O registers show as 00 like in nestest.log, not as what the open bus holds
+    fn peek(&self, a: u16) -> u8 {
+        match a {
+            0x2000..=0x401F => 0,
+            _ => self.bus.peek(a),
+        }
+    }
+
+    fn peek16(&self, lo: u16, hi: u16) -> u16 {
+        u16::from(self.peek(lo)) | (u16::from(self.peek(hi)) << 8)
+    }
+
+    // Effective address and the value currently stored there
+    fn annotate(&self, ins: &Instruction) -> String {
+        use self::AddressingMode::*;
+
+        let b1 = ins.bytes.get(1).cloned().unwrap_or(0);
+        let w = u16::from(b1) | (u16::from(ins.bytes.get(2).cloned().unwrap_or(0)) << 8);
+        let x = self.reg.read(Register::X);
+        let i = match ins.operation.mode() {
+            Some(ZeroPageX) | Some(AbsoluteX(_)) => x,
+            _ => self.reg.read(Register::Y),
+        };
+
+        match (ins.operation, ins.operation.mode()) {
+            (Operation::Jump(Some(Indirect)), _) => {
+                let hi = (w & 0xFF00) | (w.wrapping_add(1) & 0xFF);
+                format!(" = {:04X}", self.peek16(w, hi))
+            }
+            (Operation::Jump(_), _) | (_, Some(Immediate)) | (_, None) => String::new(),
+            (_, Some(ZeroPage)) => format!(" = {:02X}", self.peek(u16::from(b1))),
+            (_, Some(ZeroPageX)) | (_, Some(ZeroPageY)) => {
+                let a = b1.wrapping_add(i);
+                format!(" @ {:02X} = {:02X}", a, self.peek(u16::from(a)))
+            }
+            (_, Some(Absolute)) => format!(" = {:02X}", self.peek(w)),
+            (_, Some(AbsoluteX(_))) | (_, Some(AbsoluteY(_))) => {
+                let a = w.wrapping_add(u16::from(i));
+                format!(" @ {:04X} = {:02X}", a, self.peek(a))
+            }
+            (_, Some(IndirectX)) => {
+                let p = b1.wrapping_add(x);
+                let a = self.peek16(u16::from(p), u16::from(p.wrapping_add(1)));
+                format!(" @ {:02X} = {:04X} = {:02X}", p, a, self.peek(a))
+            }
+            (_, Some(IndirectY(_))) => {
+                let base = self.peek16(u16::from(b1), u16::from(b1.wrapping_add(1)));
+                let a = base.wrapping_add(u16::from(i));
+                format!(" = {:04X} @ {:04X} = {:02X}", base, a, self.peek(a))
+            }
+            (_, Some(Indirect)) => String::new(),
+        }
+    }
+}
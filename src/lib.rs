@@ -3,11 +3,60 @@
 #[macro_use]
 extern crate bitflags;
 
+pub mod bus;
 pub mod cart;
 pub mod cpu;
 
 pub trait MMU {
-    fn read(&self, a: u16) -> u8;
+    fn read(&mut self, a: u16) -> u8;
     fn write(&mut self, a: u16, v: u8);
     fn cycle(&mut self);
+
+    // Side effect free read for debuggers and traces
+    fn peek(&self, a: u16) -> u8;
+
+    // Address of the last access the bus could not service, cleared once taken
+    fn fault(&mut self) -> Option<u16> {
+        None
+    }
+
+    // Interrupt lines driven by the rest of the system, sampled once per cycle
+    fn nmi(&self) -> bool {
+        false
+    }
+
+    fn irq(&self) -> bool {
+        false
+    }
+}
+
+// Lets a CPU borrow a bus owned elsewhere
+impl<M: MMU + ?Sized> MMU for &mut M {
+    fn read(&mut self, a: u16) -> u8 {
+        (**self).read(a)
+    }
+
+    fn write(&mut self, a: u16, v: u8) {
+        (**self).write(a, v)
+    }
+
+    fn cycle(&mut self) {
+        (**self).cycle()
+    }
+
+    fn peek(&self, a: u16) -> u8 {
+        (**self).peek(a)
+    }
+
+    fn fault(&mut self) -> Option<u16> {
+        (**self).fault()
+    }
+
+    fn nmi(&self) -> bool {
+        (**self).nmi()
+    }
+
+    fn irq(&self) -> bool {
+        (**self).irq()
+    }
 }
@@ -0,0 +1,78 @@
+extern crate nesmesis;
+
+use nesmesis::bus::Bus;
+use nesmesis::cart;
+use nesmesis::cpu::reg::Register;
+use nesmesis::cpu::CPU;
+use nesmesis::MMU;
+
+const ROM: &[u8] = include_bytes!("nestest/nestest.nes");
+
+// nestest without PRG RAM, as a NES 2.0 dump
+fn no_ram() -> Vec<u8> {
+    let mut d = ROM.to_vec();
+    d[7] = 0x08;
+    d
+}
+
+#[test]
+fn unmapped_reads() {
+    let mut b = Bus::new(cart::load(&no_ram()).unwrap());
+
+    // Whatever last crossed the bus, read or written
+    b.write(0x0000, 0x42);
+    assert_eq!(
+        (b.read(0x4018), b.read(0x5000), b.read(0x6000)),
+        (0x42, 0x42, 0x42)
+    );
+    b.read(0xFFFC);
+    assert_eq!(b.peek(0x7FFF), ROM[16 + 0x3FFC]);
+
+    // PRG RAM and ROM drive the bus
+    let mut b = Bus::new(cart::load(ROM).unwrap());
+    b.write(0x0000, 0x42);
+    assert_eq!(b.read(0x6000), 0x00);
+}
+
+#[test]
+fn operand_on_the_bus() {
+    // LDA $5000 sees the high byte of its own operand
+    let mut b = Bus::new(cart::load(ROM).unwrap());
+    [0xAD, 0x00, 0x50]
+        .iter()
+        .enumerate()
+        .for_each(|(i, &v)| b.write(0x0200 + i as u16, v));
+
+    let mut c = CPU::new(b);
+    c.reg.write_pc(0x0200);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read(Register::A), 0x50);
+}
+
+#[test]
+fn partially_driven() {
+    // VRC2 boards without PRG RAM only drive bit 0 of their $6000 latch
+    let mut d = b"NES\x1A\x08\x04\x60\x18\x00\x00\x00".to_vec();
+    d.resize(16 + 8 * 0x4000 + 4 * 0x2000, 0);
+    let mut b = Bus::new(cart::load(&d).unwrap());
+
+    b.write(0x6000, 0x01);
+    b.write(0x0000, 0xF0);
+    assert_eq!(b.read(0x6000), 0xF1);
+}
+
+#[test]
+fn ppu_latch_decay() {
+    let mut b = Bus::new(cart::load(ROM).unwrap());
+    b.write(0x2000, 0x81);
+    b.write(0x0000, 0x00);
+    assert_eq!((b.read(0x2002), b.read(0x3FFF)), (0x81, 0x81));
+
+    // Writes reload the whole latch, which fades out after about 600 ms
+    (0..600_000).for_each(|_| b.cycle());
+    b.write(0x2003, 0x01);
+    (0..600_000).for_each(|_| b.cycle());
+    assert_eq!(b.read(0x2007), 0x01);
+    (0..600_000).for_each(|_| b.cycle());
+    assert_eq!(b.read(0x2007), 0x00);
+}
@@ -0,0 +1,160 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::bus::Bus;
+use nesmesis::cart;
+use nesmesis::cart::axrom::AxROM;
+use nesmesis::cart::cnrom::CNROM;
+use nesmesis::cart::gxrom::GxROM;
+use nesmesis::cart::nrom::NROM;
+use nesmesis::cart::uxrom::UxROM;
+use nesmesis::cart::{Mapper, Mirroring};
+use nesmesis::MMU;
+
+// Vertical mirroring, with a submapper the header is NES 2.0
+fn dump(mapper: u8, prg: u8, chr: u8, submapper: u8) -> Vec<u8> {
+    let d = Dump::new(mapper, prg, chr).flags(0x01);
+    if submapper == 0 {
+        return d.build();
+    }
+
+    d.nes2(submapper)
+        .byte(11, if chr == 0 { 0x07 } else { 0 })
+        .build()
+}
+
+#[test]
+fn load_picks_mapper() {
+    [2, 3, 7, 66].iter().for_each(|&n| {
+        let mut m = cart::load(&dump(n, 4, 4, 0)).unwrap();
+        assert_eq!(m.cpu_read(0x8000), 0, "mapper {}", n);
+    })
+}
+
+#[test]
+fn uxrom() {
+    let mut m = UxROM::new(&dump(2, 8, 0, 0)).unwrap();
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (0, 7));
+    assert_eq!(m.mirroring(), Mirroring::Vertical);
+
+    m.cpu_write(0x8000, 5);
+    assert_eq!((m.cpu_read(0xBFFF), m.cpu_read(0xFFFF)), (5, 7));
+
+    // 8K of CHR RAM
+    m.ppu_write(0x1FFF, 0x42);
+    assert_eq!(m.ppu_read(0x1FFF), 0x42);
+}
+
+#[test]
+fn chr_ram() {
+    // Homebrew NROM with 8K of CHR RAM
+    let mut m = NROM::new(&dump(0, 1, 0, 0)).unwrap();
+    m.ppu_write(0x0000, 0x11);
+    m.ppu_write(0x1FFF, 0x22);
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x1FFF)), (0x11, 0x22));
+
+    // CNROM boards wired to RAM have a single bank, whatever the register says
+    let mut m = CNROM::new(&dump(3, 1, 0, 0)).unwrap();
+    m.ppu_write(0x0400, 0x33);
+    m.cpu_write(0x8000, 1);
+    assert_eq!(m.ppu_read(0x0400), 0x33);
+
+    // NES 2.0 sizes the RAM, here 32K banked by UNROM 512
+    let mut d = dump(2, 1, 0, 1);
+    d[11] = 0x09;
+    let h = cart::header::RomHeader::parse(&d).unwrap();
+    assert_eq!(h.chr(&d).len(), 0x8000);
+}
+
+#[test]
+fn cnrom() {
+    let mut m = CNROM::new(&dump(3, 1, 4, 0)).unwrap();
+    assert_eq!(m.cpu_read(0xC000), 0);
+
+    m.cpu_write(0xFFFF, 2);
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x1FFF)), (2, 2));
+
+    // CHR ROM can't be written
+    m.ppu_write(0x0000, 0x42);
+    assert_eq!(m.ppu_read(0x0000), 2);
+}
+
+#[test]
+fn axrom() {
+    let mut m = AxROM::new(&dump(7, 8, 0, 0)).unwrap();
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xFFFF)), (0, 1));
+    assert_eq!(m.mirroring(), Mirroring::SingleScreenLower);
+
+    m.cpu_write(0x8000, 0x13);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xFFFF)), (6, 7));
+    assert_eq!(m.mirroring(), Mirroring::SingleScreenUpper);
+
+    m.ppu_write(0x0000, 0x42);
+    assert_eq!(m.ppu_read(0x0000), 0x42);
+}
+
+#[test]
+fn gxrom() {
+    let mut m = GxROM::new(&dump(66, 8, 4, 0)).unwrap();
+    m.set_bus_conflicts(false);
+
+    m.cpu_write(0x8000, 0x31);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (6, 7));
+    assert_eq!(m.ppu_read(0x1000), 1);
+}
+
+#[test]
+fn bus_conflicts() {
+    // The ROM byte under the write is the bank number, 3 in the last bank
+    let mut m = UxROM::new(&dump(2, 4, 0, 2)).unwrap();
+    m.cpu_write(0xC000, 0x06);
+    assert_eq!(m.cpu_read(0x8000), 2);
+
+    m.set_bus_conflicts(false);
+    m.cpu_write(0xC000, 0x01);
+    assert_eq!(m.cpu_read(0x8000), 1);
+
+    // Submapper 1 says there are none
+    let mut m = CNROM::new(&dump(3, 1, 4, 1)).unwrap();
+    m.cpu_write(0x8000, 3);
+    assert_eq!(m.ppu_read(0x0000), 3);
+
+    // Overridden through the trait, whatever the mapper
+    let mut m = cart::load(&dump(3, 1, 4, 0)).unwrap();
+    assert!(!m.bus_conflicts());
+    m.set_bus_conflicts(true);
+    m.cpu_write(0x8000, 3);
+    assert_eq!(m.ppu_read(0x0000), 0);
+
+    // AOROM
+    let mut m = AxROM::new(&dump(7, 4, 0, 2)).unwrap();
+    m.cpu_write(0x8000, 0x11);
+    assert_eq!(m.cpu_read(0x8000), 0);
+    assert_eq!(m.mirroring(), Mirroring::SingleScreenLower);
+
+    // GxROM always has them, the first bank is all zeroes
+    let mut m = GxROM::new(&dump(66, 8, 4, 0)).unwrap();
+    m.cpu_write(0x8000, 0x31);
+    assert_eq!((m.cpu_read(0x8000), m.ppu_read(0x0000)), (0, 0));
+}
+
+#[test]
+fn rom_is_read_only() {
+    let mut m = NROM::new(&dump(0, 1, 1, 0)).unwrap();
+    assert!(!m.rom_registers());
+    m.cpu_write(0x8000, 0x42);
+    assert_eq!(m.cpu_read(0x8000), 0);
+
+    // The bus drops writes NROM has no register for, and still hands out the others
+    let mut b = Bus::new(cart::load(&dump(0, 1, 1, 0)).unwrap());
+    b.write(0xC000, 0x42);
+    b.write(0x6000, 0x43);
+    assert_eq!((b.read(0xC000), b.read(0x6000)), (0, 0x43));
+
+    let mut b = Bus::new(cart::load(&dump(2, 4, 0, 2)).unwrap());
+    assert!(b.cart.rom_registers() && b.cart.bus_conflicts());
+    b.write(0xC000, 0x06);
+    assert_eq!((b.read(0x8000), b.read(0xC000)), (2, 3));
+}
@@ -0,0 +1,110 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::cart;
+use nesmesis::cart::fme7::FME7;
+use nesmesis::cart::{Mapper, Mirroring};
+
+fn dump(prg: u8, chr: u8) -> Vec<u8> {
+    Dump::new(69, prg, chr).banks(0x2000, 0x0400).build()
+}
+
+fn command(m: &mut dyn Mapper, c: u8, v: u8) {
+    m.cpu_write(0x8000, c);
+    m.cpu_write(0xA000, v);
+}
+
+fn audio(m: &mut FME7, r: u8, v: u8) {
+    m.cpu_write(0xC000, r);
+    m.cpu_write(0xE000, v);
+}
+
+#[test]
+fn banking() {
+    let mut cart = cart::load(&dump(8, 4)).unwrap();
+    let m = cart.as_mut();
+    command(m, 0x09, 3);
+    command(m, 0x0A, 5);
+    command(m, 0x0B, 7);
+    let banks = [0x8000, 0xA000, 0xC000, 0xE000]
+        .iter()
+        .map(|&a| m.cpu_read(a));
+    assert_eq!(banks.collect::<Vec<_>>(), [3, 5, 7, 15]);
+
+    (0..8).for_each(|i| command(m, i, 20 + i));
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x1C00)), (20, 27));
+
+    // ROM at $6000, then RAM, then RAM selected but disabled
+    command(m, 0x08, 0x02);
+    assert_eq!(m.cpu_read(0x6000), 2);
+    command(m, 0x08, 0xC0);
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+    command(m, 0x08, 0x40);
+    assert_eq!(m.cpu_read(0x6000), 0);
+
+    command(m, 0x0C, 0x03);
+    assert_eq!(m.mirroring(), Mirroring::SingleScreenUpper);
+}
+
+#[test]
+fn irq() {
+    let mut m = FME7::new(&dump(8, 4)).unwrap();
+    command(&mut m, 0x0E, 0x02);
+    command(&mut m, 0x0F, 0x00);
+    command(&mut m, 0x0D, 0x81);
+
+    // Fires when the counter wraps from 0 to $FFFF
+    m.cycle();
+    m.cycle();
+    assert!(!m.irq());
+    m.cycle();
+    assert!(m.irq());
+
+    // Any write to the control acknowledges, and the counter can run without IRQs
+    command(&mut m, 0x0D, 0x80);
+    assert!(!m.irq());
+    (0..0x10000).for_each(|_| m.cycle());
+    assert!(!m.irq());
+}
+
+#[test]
+fn audio_tone() {
+    let mut m = FME7::new(&dump(8, 4)).unwrap();
+    assert_eq!(m.audio(), 0.0);
+
+    // Channel A alone at full volume, flipping every 16 cycles
+    audio(&mut m, 0x00, 0x01);
+    audio(&mut m, 0x07, 0x3E);
+    audio(&mut m, 0x08, 0x0F);
+    m.cycle();
+    assert!((m.audio() - 0.1494).abs() < 1e-6);
+    (0..16).for_each(|_| m.cycle());
+    assert_eq!(m.audio(), 0.0);
+
+    // Each volume step is 3 dB
+    audio(&mut m, 0x07, 0x3F);
+    audio(&mut m, 0x08, 0x0D);
+    assert!((m.audio() - 0.1494 * 10f32.powf(-6.0 / 20.0)).abs() < 1e-6);
+}
+
+#[test]
+fn audio_envelope() {
+    let mut m = FME7::new(&dump(8, 4)).unwrap();
+    audio(&mut m, 0x07, 0x3F);
+    audio(&mut m, 0x08, 0x10);
+    audio(&mut m, 0x0B, 0x01);
+
+    // Rising, then held at the top
+    audio(&mut m, 0x0D, 0x0D);
+    assert_eq!(m.audio(), 0.0);
+    (0..8 * 40).for_each(|_| m.cycle());
+    assert!((m.audio() - 0.1494).abs() < 1e-6);
+
+    // Falling once, then silent
+    audio(&mut m, 0x0D, 0x00);
+    (0..8 * 40).for_each(|_| m.cycle());
+    assert_eq!(m.audio(), 0.0);
+}
@@ -0,0 +1,171 @@
+extern crate nesmesis;
+
+use nesmesis::cart::header::{Console, HeaderError, RomHeader, Timing};
+use nesmesis::cart::Mirroring;
+
+// Header with the given bytes 4-9, followed by the PRG and CHR data it declares
+fn dump(flags: [u8; 6], trainer: bool) -> Vec<u8> {
+    let mut d = b"NES\x1A".to_vec();
+    d.extend_from_slice(&flags);
+    d.resize(16, 0);
+
+    let size = if trainer { 512 } else { 0 }
+        + usize::from(flags[0]) * 16384
+        + usize::from(flags[1]) * 8192;
+    d.resize(16 + size, 0xFF);
+    d
+}
+
+#[test]
+fn nestest_header() {
+    let d = include_bytes!("nestest/nestest.nes");
+    let h = RomHeader::parse(d).unwrap();
+
+    assert_eq!(h.prg_rom_size, 16384);
+    assert_eq!(h.chr_rom_size, 8192);
+    assert_eq!(h.prg_ram_size, 8192);
+    assert_eq!(h.mapper, 0);
+    assert_eq!(h.mirroring, Mirroring::Horizontal);
+    assert!(!h.battery && !h.trainer && !h.nes2);
+    assert_eq!(h.timing, Timing::Ntsc);
+    assert_eq!(h.chr_ram_size, 0);
+    assert_eq!(h.file_size(), d.len());
+}
+
+#[test]
+fn flags() {
+    // MMC1, battery, trainer, vertical, VS, 2 PRG RAM pages, PAL
+    let d = dump([2, 1, 0x17, 0x01, 2, 1], true);
+    let h = RomHeader::parse(&d).unwrap();
+
+    assert_eq!(h.mapper, 1);
+    assert_eq!(h.mirroring, Mirroring::Vertical);
+    assert!(h.battery && h.trainer);
+    assert_eq!(
+        h.console,
+        Console::VsSystem {
+            ppu: 0,
+            hardware: 0
+        }
+    );
+    assert_eq!(h.prg_ram_size, 16384);
+    assert_eq!(h.timing, Timing::Pal);
+    assert_eq!(h.prg_offset(), 16 + 512);
+    assert_eq!(h.prg_rom(&d).len(), 32768);
+    assert_eq!(h.trainer(&d).map(|t| t.len()), Some(512));
+
+    // MMC3 with four screen VRAM, the high nibble comes from flags 7
+    let h = RomHeader::parse(&dump([1, 0, 0x48, 0x02, 0, 0], false)).unwrap();
+    assert_eq!(h.mapper, 4);
+    assert_eq!(h.mirroring, Mirroring::FourScreen);
+    assert_eq!(h.console, Console::Playchoice);
+
+    let h = RomHeader::parse(&dump([1, 0, 0x20, 0x40, 0, 0], false)).unwrap();
+    assert_eq!(h.mapper, 0x42);
+}
+
+#[test]
+fn nes2_header() {
+    let mut d = dump([2, 0, 0x12, 0x48, 0x15, 0x00], false);
+    d[10..16].copy_from_slice(&[0x70, 0x07, 0x03, 0x00, 0x01, 0x23]);
+    let h = RomHeader::parse(&d).unwrap();
+
+    assert!(h.nes2);
+    assert_eq!(h.mapper, 0x541);
+    assert_eq!(h.submapper, 1);
+    assert_eq!(h.prg_rom_size, 32768);
+    assert_eq!(h.chr_rom_size, 0);
+    assert_eq!((h.prg_ram_size, h.prg_nvram_size), (0, 8192));
+    assert_eq!((h.chr_ram_size, h.chr_nvram_size), (8192, 0));
+    assert_eq!(h.prg_ram_total(), 8192);
+    assert_eq!(h.console, Console::Nes);
+    assert_eq!(h.timing, Timing::Dendy);
+    assert_eq!(h.misc_roms, 1);
+    assert_eq!(h.expansion, 0x23);
+
+    // VS System with its PPU and hardware types, and an extended console type
+    d[7] = 0x09;
+    d[13] = 0x34;
+    assert_eq!(
+        RomHeader::parse(&d).unwrap().console,
+        Console::VsSystem {
+            ppu: 4,
+            hardware: 3
+        }
+    );
+    d[7] = 0x0B;
+    assert_eq!(RomHeader::parse(&d).unwrap().console, Console::Extended(4));
+}
+
+#[test]
+fn nes2_rom_sizes() {
+    // 1 MiB of PRG through the MSB nibble, 24K of CHR as 2^13 * 3
+    let mut d = dump([0x40, 0, 0x00, 0x08, 0, 0], false);
+    d[5] = 0x35;
+    d[9] = 0xF0;
+    d.resize(16 + 0x40 * 16384 + 24576, 0);
+
+    let h = RomHeader::parse(&d).unwrap();
+    assert_eq!(h.prg_rom_size, 0x40 * 16384);
+    assert_eq!(h.chr_rom_size, 24576);
+    assert_eq!(h.file_size(), d.len());
+
+    // The MSB nibble adds 256 pages
+    d[9] = 0xF1;
+    assert_eq!(
+        RomHeader::parse(&d),
+        Err(HeaderError::Truncated {
+            expected: 16 + 0x140 * 16384 + 24576,
+            found: d.len()
+        })
+    );
+
+    // A nonsense exponent is reported as truncated rather than overflowing
+    d[4] = 0xFF;
+    d[9] = 0x0F;
+    assert!(RomHeader::parse(&d).is_err());
+}
+
+#[test]
+fn garbage_in_padding() {
+    let mut d = dump([1, 1, 0x10, 0x44, 0x69, 0x73], false);
+    d[7..16].copy_from_slice(b"DiskDude!");
+
+    let h = RomHeader::parse(&d).unwrap();
+    assert_eq!(h.mapper, 1);
+    assert_eq!(h.prg_ram_size, 8192);
+    assert_eq!(h.console, Console::Nes);
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        RomHeader::parse(b"NES\x1A"),
+        Err(HeaderError::Truncated {
+            expected: 16,
+            found: 4
+        })
+    );
+
+    let mut d = dump([1, 1, 0, 0, 0, 0], false);
+    d[3] = 0;
+    assert_eq!(RomHeader::parse(&d), Err(HeaderError::BadMagic));
+
+    let d = dump([0, 1, 0, 0, 0, 0], false);
+    assert_eq!(RomHeader::parse(&d), Err(HeaderError::NoPrgRom));
+
+    let mut d = dump([2, 1, 0, 0, 0, 0], false);
+    d.truncate(20000);
+    let e = RomHeader::parse(&d).unwrap_err();
+    assert_eq!(
+        e,
+        HeaderError::Truncated {
+            expected: 16 + 32768 + 8192,
+            found: 20000
+        }
+    );
+    assert_eq!(
+        e.to_string(),
+        "ROM is truncated: header declares 40976 bytes but the file has 20000"
+    );
+}
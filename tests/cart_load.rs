@@ -0,0 +1,132 @@
+extern crate nesmesis;
+
+use std::error::Error;
+
+use nesmesis::bus::Bus;
+use nesmesis::cart;
+use nesmesis::cart::error::CartError;
+use nesmesis::cart::header::HeaderError;
+use nesmesis::cpu::CPU;
+
+const ROM: &[u8] = include_bytes!("nestest/nestest.nes");
+
+fn assert_send<T: Send>() {}
+
+#[test]
+fn loads_nrom() {
+    let mut m = cart::load(ROM).unwrap();
+
+    // Reset vector from the last bytes of the mirrored 16K bank
+    let prg = &ROM[16..16 + 16384];
+    assert_eq!(m.cpu_read(0xFFFC), prg[0x3FFC]);
+    assert_eq!(m.cpu_read(0xBFFD), prg[0x3FFD]);
+}
+
+#[test]
+fn unsupported_mapper() {
+    let mut d = ROM.to_vec();
+    d[6] |= 0xF0;
+    d[7] |= 0x40;
+
+    let e = cart::load(&d).err().unwrap();
+    assert_eq!(
+        e,
+        CartError::UnsupportedMapper {
+            mapper: 0x4F,
+            submapper: 0
+        }
+    );
+    assert_eq!(e.to_string(), "Unsupported mapper 79");
+
+    // NES 2.0 submapper 2 of mapper 271
+    d[7] = 0x08;
+    d[8] = 0x21;
+    let e = cart::load(&d).err().unwrap();
+    assert_eq!(e.to_string(), "Unsupported mapper 271.2");
+}
+
+#[test]
+fn bad_header() {
+    let e = cart::load(&ROM[..1000]).err().unwrap();
+    assert_eq!(
+        e,
+        CartError::Header(HeaderError::Truncated {
+            expected: ROM.len(),
+            found: 1000
+        })
+    );
+    assert!(e.source().is_some());
+}
+
+#[test]
+fn trainer() {
+    let mut d = ROM[..16].to_vec();
+    d[6] |= 0x04;
+    d.extend((0..512).map(|i| i as u8));
+    d.extend_from_slice(&ROM[16..]);
+
+    // PRG ROM still starts after the trainer, which sits at $7000-$71FF
+    let mut m = cart::load(&d).unwrap();
+    assert_eq!(m.cpu_read(0xFFFC), ROM[16 + 0x3FFC]);
+    assert_eq!((m.cpu_read(0x6FFF), m.cpu_read(0x7000)), (0x00, 0x00));
+    assert_eq!((m.cpu_read(0x7001), m.cpu_read(0x71FF)), (0x01, 0xFF));
+    assert_eq!(m.cpu_read(0x7200), 0x00);
+}
+
+#[test]
+fn undersized_roms() {
+    // NES 2.0 exponent sizes: 4K of PRG ROM and 1K of CHR ROM, less than a bank anywhere
+    let mappers: [u8; 19] = [0, 1, 2, 3, 4, 5, 7, 9, 10, 19, 21, 22, 23, 24, 25, 26, 66, 69, 85];
+
+    mappers.iter().for_each(|&n| {
+        let mut d = b"NES\x1A".to_vec();
+        d.extend_from_slice(&[12 << 2, 10 << 2, n << 4, (n & 0xF0) | 0x08, 0x00, 0xFF]);
+        d.resize(16, 0);
+        d.extend((0..0x1400).map(|i| (i >> 8) as u8));
+
+        let mut m = cart::load(&d).unwrap();
+        assert_eq!(m.cpu_read(0xFFFC), 0x0F, "mapper {}", n);
+
+        (0x6000..=0xFFFF).for_each(|a| {
+            m.cpu_write(a, 0xFF);
+            m.cpu_read(a);
+        });
+        (0x0000..0x3F00).for_each(|a| {
+            m.ppu_read(a);
+        });
+    })
+}
+
+#[test]
+fn trainer_without_prg_ram() {
+    // 32K of PRG and 8K of CHR on boards that have no PRG RAM of their own
+    let dump = |mapper: u8, trainer: bool| {
+        let mut d = b"NES\x1A".to_vec();
+        d.extend_from_slice(&[2, 1, mapper << 4, mapper & 0xF0]);
+        d.resize(16, 0);
+        if trainer {
+            d[6] |= 0x04;
+            d.extend((0..512).map(|i| i as u8));
+        }
+        d.extend(vec![0; 0xA000]);
+        d
+    };
+
+    [2, 3, 7, 9, 66].iter().for_each(|&n| {
+        let mut m = cart::load(&dump(n, true)).unwrap();
+        assert_eq!((m.cpu_read(0x7001), m.cpu_read(0x71FF)), (0x01, 0xFF), "mapper {}", n);
+        m.cpu_write(0x6000, 0x42);
+        assert_eq!(m.cpu_read(0x6000), 0x42, "mapper {}", n);
+        assert_eq!(m.cpu_driven(0x6000), 0xFF, "mapper {}", n);
+
+        // Without one $6000-$7FFF stays open bus
+        let m = cart::load(&dump(n, false)).unwrap();
+        assert_eq!(m.cpu_driven(0x6000), 0x00, "mapper {}", n);
+    })
+}
+
+#[test]
+fn cpu_with_a_cart_is_send() {
+    // The whole system can be handed to another thread
+    assert_send::<CPU<Bus>>();
+}
@@ -0,0 +1,191 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::cart;
+use nesmesis::cart::mmc1::{Board, MMC1};
+use nesmesis::cart::{Mapper, Mirroring};
+
+// With a submapper the header is NES 2.0, `ram` counts 8K units either way
+fn dump(prg: u8, chr: u8, ram: u8, submapper: u8) -> Vec<u8> {
+    let d = Dump::new(1, prg, chr).banks(0x4000, 0x1000).flags(0x02);
+    if submapper == 0 {
+        return d.byte(8, ram).build();
+    }
+
+    d.nes2(submapper)
+        .byte(10, if ram == 0 { 0 } else { 0x07 + ram / 2 })
+        .byte(11, if chr == 0 { 0x07 } else { 0 })
+        .build()
+}
+
+// Five serial writes a few cycles apart, low bit first
+fn write(m: &mut MMC1, a: u16, v: u8) {
+    (0..5).for_each(|i| {
+        m.cycle();
+        m.cycle();
+        m.cpu_write(a, v >> i);
+    })
+}
+
+#[test]
+fn loads_mmc1() {
+    let d = dump(8, 2, 1, 0);
+    let mut m = cart::load(&d).unwrap();
+    assert_eq!(m.cpu_read(0x8000), 0);
+    assert_eq!(m.cpu_read(0xC000), 7);
+}
+
+#[test]
+fn prg_modes() {
+    let mut m = MMC1::new(&dump(8, 2, 1, 0)).unwrap();
+
+    // Power on in mode 3, last bank fixed at $C000
+    write(&mut m, 0xE000, 5);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xFFFF)), (5, 7));
+
+    // Mode 2, first bank fixed at $8000
+    write(&mut m, 0x8000, 0x08);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (0, 5));
+
+    // 32K mode ignores the low bit
+    write(&mut m, 0x8000, 0x00);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (4, 5));
+}
+
+#[test]
+fn chr_modes() {
+    let mut m = MMC1::new(&dump(2, 4, 1, 0)).unwrap();
+
+    write(&mut m, 0xA000, 3);
+    write(&mut m, 0xC000, 6);
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x1000)), (2, 3));
+
+    write(&mut m, 0x8000, 0x1C);
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x1FFF)), (3, 6));
+}
+
+#[test]
+fn mirroring() {
+    let mut m = MMC1::new(&dump(2, 1, 1, 0)).unwrap();
+
+    let modes = [
+        Mirroring::SingleScreenLower,
+        Mirroring::SingleScreenUpper,
+        Mirroring::Vertical,
+        Mirroring::Horizontal,
+    ];
+
+    modes.iter().enumerate().for_each(|(i, &mode)| {
+        write(&mut m, 0x9FFF, 0x0C | i as u8);
+        assert_eq!(m.mirroring(), mode);
+    })
+}
+
+#[test]
+fn reset_and_consecutive_writes() {
+    let mut m = MMC1::new(&dump(8, 1, 1, 0)).unwrap();
+    write(&mut m, 0x8000, 0x00);
+
+    // Three bits in, then a reset clears the shift register and restores mode 3
+    (0..3).for_each(|_| {
+        m.cycle();
+        m.cycle();
+        m.cpu_write(0xE000, 1);
+    });
+    m.cycle();
+    m.cycle();
+    m.cpu_write(0xE000, 0x80);
+    write(&mut m, 0xE000, 2);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (2, 7));
+
+    // INC $E000 writes twice on back to back cycles, only the first counts
+    (0..5).for_each(|_| {
+        m.cycle();
+        m.cycle();
+        m.cpu_write(0xE000, 0x01);
+        m.cycle();
+        m.cpu_write(0xE000, 0x00);
+    });
+    // $1F selects bank 15, which wraps to 7, and disables PRG RAM
+    assert_eq!(m.cpu_read(0x8000), 7);
+    assert_eq!(m.cpu_read(0x6000), 0);
+}
+
+#[test]
+fn prg_ram_enable() {
+    let mut m = MMC1::new(&dump(2, 1, 1, 0)).unwrap();
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+
+    write(&mut m, 0xE000, 0x10);
+    assert_eq!(m.cpu_read(0x6000), 0);
+    m.cpu_write(0x6000, 0x43);
+
+    write(&mut m, 0xE000, 0x00);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+}
+
+#[test]
+fn boards() {
+    assert_eq!(
+        MMC1::new(&dump(8, 2, 1, 0)).unwrap().board(),
+        Board::Standard
+    );
+    assert_eq!(MMC1::new(&dump(16, 0, 1, 0)).unwrap().board(), Board::Snrom);
+    assert_eq!(MMC1::new(&dump(16, 0, 2, 0)).unwrap().board(), Board::Sorom);
+    assert_eq!(MMC1::new(&dump(32, 0, 1, 0)).unwrap().board(), Board::Surom);
+    assert_eq!(MMC1::new(&dump(32, 0, 4, 0)).unwrap().board(), Board::Sxrom);
+    assert_eq!(MMC1::new(&dump(8, 0, 1, 2)).unwrap().board(), Board::Sorom);
+    assert_eq!(MMC1::new(&dump(16, 0, 1, 4)).unwrap().board(), Board::Sxrom);
+    assert_eq!(MMC1::new(&dump(16, 0, 1, 3)).unwrap().board(), Board::Snrom);
+}
+
+#[test]
+fn snrom_ram_disable() {
+    let mut m = MMC1::new(&dump(16, 0, 1, 0)).unwrap();
+    m.cpu_write(0x6000, 0x42);
+
+    write(&mut m, 0xA000, 0x10);
+    assert_eq!(m.cpu_read(0x6000), 0);
+    write(&mut m, 0xA000, 0x00);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+}
+
+#[test]
+fn sorom_ram_banks() {
+    let mut m = MMC1::new(&dump(16, 0, 2, 0)).unwrap();
+    m.cpu_write(0x6000, 0x01);
+
+    write(&mut m, 0xA000, 0x08);
+    assert_eq!(m.cpu_read(0x6000), 0);
+    m.cpu_write(0x6000, 0x02);
+
+    write(&mut m, 0xA000, 0x00);
+    assert_eq!(m.cpu_read(0x6000), 0x01);
+}
+
+#[test]
+fn surom_outer_bank() {
+    let mut m = MMC1::new(&dump(32, 0, 1, 0)).unwrap();
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (0, 15));
+
+    write(&mut m, 0xA000, 0x10);
+    write(&mut m, 0xE000, 0x03);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (19, 31));
+}
+
+#[test]
+fn sxrom_ram_banks() {
+    let mut m = MMC1::new(&dump(32, 0, 4, 0)).unwrap();
+
+    (0..4).for_each(|b| {
+        write(&mut m, 0xA000, 0x10 | (b << 2));
+        m.cpu_write(0x7FFF, b);
+    });
+
+    write(&mut m, 0xA000, 0x18);
+    assert_eq!(m.cpu_read(0x7FFF), 2);
+    assert_eq!(m.cpu_read(0xC000), 31);
+}
@@ -0,0 +1,82 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::cart;
+use nesmesis::cart::mmc2::{MMC2, MMC4};
+use nesmesis::cart::{Mapper, Mirroring};
+
+fn dump(mapper: u8, prg: u8, chr: u8) -> Vec<u8> {
+    Dump::new(mapper, prg, chr).banks(0x2000, 0x1000).build()
+}
+
+// $FD and $FE banks 1 and 2 on the left table, 3 and 4 on the right one
+fn setup<M: Mapper>(m: &mut M) {
+    m.cpu_write(0xB000, 1);
+    m.cpu_write(0xC000, 2);
+    m.cpu_write(0xD000, 3);
+    m.cpu_write(0xE000, 4);
+}
+
+#[test]
+fn mmc2_prg() {
+    let mut m = cart::load(&dump(9, 8, 8)).unwrap();
+    assert_eq!(m.cpu_read(0x8000), 0);
+    assert_eq!(m.cpu_read(0xA000), 13);
+    assert_eq!(m.cpu_read(0xFFFF), 15);
+
+    m.cpu_write(0xA000, 5);
+    assert_eq!(m.cpu_read(0x9FFF), 5);
+
+    // Less than the four banks the fixed ones are counted back from
+    let mut m = cart::load(&dump(9, 1, 8)).unwrap();
+    assert_eq!((m.cpu_read(0xA000), m.cpu_read(0xFFFF)), (1, 1));
+}
+
+#[test]
+fn mmc2_latches() {
+    let mut m = MMC2::new(&dump(9, 8, 8)).unwrap();
+    setup(&mut m);
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x1000)), (2, 4));
+
+    // The fetch that flips the latch still comes from the old bank
+    assert_eq!(m.ppu_read(0x0FD8), 2);
+    assert_eq!(m.ppu_read(0x0000), 1);
+    assert_eq!(m.ppu_read(0x1000), 4);
+
+    // Only $0FD8 itself on the left, a whole row on the right
+    m.ppu_read(0x0FE9);
+    assert_eq!(m.ppu_read(0x0000), 1);
+    m.ppu_read(0x1FDF);
+    assert_eq!(m.ppu_read(0x1000), 3);
+    m.ppu_read(0x1FEA);
+    assert_eq!(m.ppu_read(0x1000), 4);
+}
+
+#[test]
+fn mmc4() {
+    let mut m = MMC4::new(&dump(10, 8, 8)).unwrap();
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (0, 14));
+
+    m.cpu_write(0xA000, 3);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xA000)), (6, 7));
+
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+
+    // The left table reacts to the whole row too
+    setup(&mut m);
+    m.ppu_read(0x0FDC);
+    assert_eq!(m.ppu_read(0x0000), 1);
+    m.ppu_read(0x0FEF);
+    assert_eq!(m.ppu_read(0x0000), 2);
+}
+
+#[test]
+fn mirroring() {
+    let mut m = MMC2::new(&dump(9, 8, 8)).unwrap();
+    assert_eq!(m.mirroring(), Mirroring::Vertical);
+    m.cpu_write(0xF000, 1);
+    assert_eq!(m.mirroring(), Mirroring::Horizontal);
+}
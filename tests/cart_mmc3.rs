@@ -0,0 +1,186 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::bus::Bus;
+use nesmesis::cart;
+use nesmesis::cart::mmc3::{Revision, MMC3};
+use nesmesis::cart::{Mapper, Mirroring};
+use nesmesis::cpu::CPU;
+use nesmesis::MMU;
+
+// NES 2.0 dump with 8K of PRG RAM and `code` at $E000, in the last PRG bank
+fn dump(prg: u8, chr: u8, submapper: u8, code: &[u8]) -> Vec<u8> {
+    let mut d = Dump::new(4, prg, chr)
+        .banks(0x2000, 0x0400)
+        .nes2(submapper)
+        .byte(10, 0x07)
+        .build();
+
+    let last = 16 + usize::from(prg) * 0x4000 - 0x2000;
+    d[last..last + code.len()].copy_from_slice(code);
+
+    // Reset at $E000, IRQ at $E100
+    d[last + 0x1FFC..last + 0x2000].copy_from_slice(&[0x00, 0xE0, 0x00, 0xE1]);
+    d
+}
+
+// A12 low for a while, then high
+fn scanline(m: &mut MMC3) {
+    m.ppu_bus(0x0000);
+    (0..4).for_each(|_| m.cycle());
+    m.ppu_bus(0x1000);
+}
+
+#[test]
+fn prg_banking() {
+    let mut m = MMC3::new(&dump(8, 8, 0, &[])).unwrap();
+
+    m.cpu_write(0x8000, 6);
+    m.cpu_write(0x8001, 3);
+    m.cpu_write(0x8000, 7);
+    m.cpu_write(0x8001, 5);
+    assert_eq!(m.cpu_read(0x8000), 3);
+    assert_eq!(m.cpu_read(0xA000), 5);
+    assert_eq!(m.cpu_read(0xC000), 14);
+    assert_eq!(m.cpu_read(0xE000 + 0x1000), 15);
+
+    // PRG mode 1 swaps $8000 and $C000
+    m.cpu_write(0x8000, 0x40);
+    assert_eq!(m.cpu_read(0x8000), 14);
+    assert_eq!(m.cpu_read(0xC000), 3);
+}
+
+#[test]
+fn chr_banking() {
+    let mut m = MMC3::new(&dump(2, 8, 0, &[])).unwrap();
+
+    (0..6).for_each(|r| {
+        m.cpu_write(0x8000, r);
+        m.cpu_write(0x8001, 10 + r * 8);
+    });
+
+    let banks = [10, 11, 18, 19, 26, 34, 42, 50];
+    banks.iter().enumerate().for_each(|(i, &b)| {
+        assert_eq!(m.ppu_read(i as u16 * 0x400), b);
+    });
+
+    // A12 inversion
+    m.cpu_write(0x8000, 0x80);
+    assert_eq!(m.ppu_read(0x0000), 26);
+    assert_eq!(m.ppu_read(0x1C00), 19);
+}
+
+#[test]
+fn mirroring_and_ram() {
+    let mut m = MMC3::new(&dump(2, 8, 0, &[])).unwrap();
+
+    assert_eq!(m.mirroring(), Mirroring::Vertical);
+    m.cpu_write(0xA000, 1);
+    assert_eq!(m.mirroring(), Mirroring::Horizontal);
+
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+
+    // Write protected, then disabled
+    m.cpu_write(0xA001, 0xC0);
+    m.cpu_write(0x6000, 0x43);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+    m.cpu_write(0xA001, 0x00);
+    assert_eq!(m.cpu_read(0x6000), 0);
+}
+
+#[test]
+fn irq_counter() {
+    let mut m = MMC3::new(&dump(2, 8, 0, &[])).unwrap();
+    m.cpu_write(0xC000, 2);
+    m.cpu_write(0xC001, 0);
+    m.cpu_write(0xE001, 0);
+
+    // Reload to 2, then 1, then 0
+    scanline(&mut m);
+    scanline(&mut m);
+    assert!(!m.irq());
+    scanline(&mut m);
+    assert!(m.irq());
+
+    // Held until acknowledged
+    scanline(&mut m);
+    assert!(m.irq());
+    m.cpu_write(0xE000, 0);
+    assert!(!m.irq());
+}
+
+#[test]
+fn a12_filter() {
+    let mut m = MMC3::new(&dump(2, 8, 0, &[])).unwrap();
+    m.cpu_write(0xC000, 0);
+    m.cpu_write(0xE001, 0);
+    (0..4).for_each(|_| m.cycle());
+
+    m.ppu_bus(0x1000);
+    assert!(m.irq());
+    m.cpu_write(0xE000, 0);
+    m.cpu_write(0xE001, 0);
+
+    // Sprite fetches toggle A12 faster than the filter lets through
+    m.ppu_bus(0x0000);
+    m.cycle();
+    m.ppu_bus(0x1000);
+    assert!(!m.irq());
+}
+
+#[test]
+fn revisions() {
+    // With a latch of 0, Rev B fires on every clock and Rev A only after a reload
+    let mut d = dump(2, 8, 0, &[]);
+    let mut b = MMC3::new(&d).unwrap();
+    d[8] = 0x40;
+    let mut a = MMC3::new(&d).unwrap();
+    assert_eq!((a.revision(), b.revision()), (Revision::A, Revision::B));
+
+    [&mut a, &mut b].iter_mut().for_each(|m| {
+        m.cpu_write(0xC000, 0);
+        m.cpu_write(0xC001, 0);
+        m.cpu_write(0xE001, 0);
+        scanline(m);
+        assert!(m.irq());
+        m.cpu_write(0xE000, 0);
+        m.cpu_write(0xE001, 0);
+        scanline(m);
+    });
+
+    assert!(!a.irq());
+    assert!(b.irq());
+
+    a.set_revision(Revision::B);
+    scanline(&mut a);
+    assert!(a.irq());
+}
+
+#[test]
+fn irq_reaches_cpu() {
+    // CLI, then JMP to itself
+    let d = dump(2, 8, 0, &[0x58, 0x4C, 0x01, 0xE0]);
+    let mut c = CPU::new(Bus::new(cart::load(&d).unwrap()));
+    c.init();
+
+    c.bus.write(0xC000, 1);
+    c.bus.write(0xC001, 0);
+    c.bus.write(0xE001, 0);
+    c.execute().unwrap();
+
+    c.bus.cart.ppu_bus(0x0000);
+    c.execute().unwrap();
+    c.bus.cart.ppu_bus(0x1000);
+    c.execute().unwrap();
+    c.bus.cart.ppu_bus(0x0000);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0xE001);
+
+    c.bus.cart.ppu_bus(0x1000);
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0xE100);
+}
@@ -0,0 +1,206 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::cart;
+use nesmesis::cart::mmc5::MMC5;
+use nesmesis::cart::{Mapper, Mirroring};
+
+// NES 2.0 dump with 64K of PRG RAM
+fn dump(prg: u8, chr: u8) -> Vec<u8> {
+    Dump::new(5, prg, chr)
+        .banks(0x2000, 0x0400)
+        .nes2(0)
+        .byte(10, 0x0A)
+        .build()
+}
+
+// Fetches of one rendered scanline, starting with the two dummy reads that end the
+// previous one
+fn line(m: &mut MMC5) -> Vec<u8> {
+    let mut v = vec![m.ppu_read(0x2002), m.ppu_read(0x2002)];
+
+    for t in (2..34).chain(0..8).chain(0..2) {
+        let sprite = v.len() >= 130 && v.len() < 162;
+        let nt = if sprite { 0x2000 } else { 0x2000 | (t % 32) };
+        let at = if sprite { 0x2000 } else { 0x23C0 };
+        let pt = if sprite { 0x0000 } else { 0x1000 };
+        v.extend(vec![
+            m.ppu_read(nt),
+            m.ppu_read(at),
+            m.ppu_read(pt),
+            m.ppu_read(pt | 8),
+        ]);
+    }
+    v
+}
+
+#[test]
+fn prg_modes() {
+    let mut m = cart::load(&dump(8, 8)).unwrap();
+
+    // Mode 3 at power on, with the last bank at $E000
+    assert_eq!(m.cpu_read(0xE000), 15);
+    m.cpu_write(0x5114, 0x83);
+    m.cpu_write(0x5115, 0x85);
+    m.cpu_write(0x5116, 0x87);
+    assert_eq!(
+        [m.cpu_read(0x8000), m.cpu_read(0xA000), m.cpu_read(0xC000)],
+        [3, 5, 7]
+    );
+
+    // 16K windows ignore the low bit
+    m.cpu_write(0x5100, 1);
+    assert_eq!(
+        [m.cpu_read(0x8000), m.cpu_read(0xA000), m.cpu_read(0xC000)],
+        [4, 5, 14]
+    );
+
+    m.cpu_write(0x5100, 0);
+    m.cpu_write(0x5117, 0x05);
+    assert_eq!([m.cpu_read(0x8000), m.cpu_read(0xE000)], [4, 7]);
+}
+
+#[test]
+fn prg_ram() {
+    let mut m = MMC5::new(&dump(8, 8)).unwrap();
+    m.cpu_write(0x5113, 2);
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0);
+
+    m.cpu_write(0x5102, 2);
+    m.cpu_write(0x5103, 1);
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+
+    // The same RAM bank mapped at $8000
+    m.cpu_write(0x5114, 0x02);
+    assert_eq!(m.cpu_read(0x8000), 0x42);
+    m.cpu_write(0x5113, 3);
+    assert_eq!(m.cpu_read(0x6000), 0);
+}
+
+#[test]
+fn chr_sets() {
+    let mut m = MMC5::new(&dump(2, 8)).unwrap();
+    m.cpu_write(0x5101, 3);
+    (0..12).for_each(|r| m.cpu_write(0x5120 + r, 10 + r as u8));
+
+    // Outside rendering, the last written set wins
+    assert_eq!([m.ppu_read(0x0000), m.ppu_read(0x1C00)], [18, 21]);
+    m.cpu_write(0x5127, 17);
+    assert_eq!([m.ppu_read(0x0000), m.ppu_read(0x1C00)], [10, 17]);
+
+    // 8x16 sprites fetch from $5120-$5127 and the background from $5128-$512B
+    m.ppu_register(0x2000, 0x20);
+    line(&mut m);
+    let v = line(&mut m);
+    assert_eq!(v[4], 18);
+    assert_eq!(v[2 + 128 + 2], 10);
+
+    // Back to the last written set once rendering is turned off
+    m.ppu_register(0x2001, 0x00);
+    assert_eq!(m.ppu_read(0x1000), 14);
+
+    // In 8K mode $512B picks a whole 8K bank for the background too
+    m.cpu_write(0x5101, 0);
+    m.cpu_write(0x512B, 2);
+    assert_eq!([m.ppu_read(0x0000), m.ppu_read(0x1C00)], [16, 23]);
+}
+
+#[test]
+fn nametables() {
+    let mut m = MMC5::new(&dump(2, 8)).unwrap();
+    assert_eq!(m.mirroring(), Mirroring::SingleScreenLower);
+
+    // CIRAM pages, ExRAM and fill mode
+    m.cpu_write(0x5105, 0xE4);
+    assert_eq!(m.mirroring(), Mirroring::MapperControlled);
+    m.cpu_write(0x5106, 0x33);
+    m.cpu_write(0x5107, 0x02);
+    [0x2000, 0x2400, 0x2800]
+        .iter()
+        .enumerate()
+        .for_each(|(i, &a)| {
+            m.ppu_write(a, i as u8 + 1);
+        });
+    assert_eq!(m.ppu_read(0x2000), 1);
+    assert_eq!(m.ppu_read(0x2400), 2);
+    assert_eq!(m.ppu_read(0x2800), 3);
+    assert_eq!([m.ppu_read(0x2C00), m.ppu_read(0x2FC0)], [0x33, 0xAA]);
+
+    // ExRAM through the CPU
+    m.cpu_write(0x5104, 2);
+    m.cpu_write(0x5C01, 0x42);
+    assert_eq!(m.cpu_read(0x5C01), 0x42);
+    assert_eq!(m.ppu_read(0x2801), 0);
+    m.cpu_write(0x5104, 3);
+    m.cpu_write(0x5C01, 0x43);
+    assert_eq!(m.cpu_read(0x5C01), 0x42);
+}
+
+#[test]
+fn extended_attributes() {
+    let mut m = MMC5::new(&dump(2, 16)).unwrap();
+    m.cpu_write(0x5104, 2);
+    m.cpu_write(0x5C00 + 3, 0xC3);
+    m.cpu_write(0x5104, 1);
+
+    line(&mut m);
+    let v = line(&mut m);
+    // Tile 3: palette 3 and 4K bank 3
+    assert_eq!(&v[6..10], &[0, 0xFF, 12, 12]);
+    assert_eq!(v[3], 0);
+}
+
+#[test]
+fn split_screen() {
+    let mut m = MMC5::new(&dump(2, 16)).unwrap();
+    m.cpu_write(0x5104, 2);
+    m.cpu_write(0x5C00 + 32 + 2, 0x12);
+    m.cpu_write(0x5C00 + 0x3C0, 0x0C);
+    m.cpu_write(0x5104, 0);
+
+    // Left of tile 3, rows scrolled by 8 and CHR from the 4K bank 2
+    m.cpu_write(0x5200, 0x83);
+    m.cpu_write(0x5201, 8);
+    m.cpu_write(0x5202, 2);
+
+    line(&mut m);
+    let v = line(&mut m);
+    assert_eq!(&v[2..6], &[0x12, 0xFF, 8, 8]);
+    assert_eq!(v[6], 0);
+}
+
+#[test]
+fn multiplier() {
+    let mut m = MMC5::new(&dump(2, 8)).unwrap();
+    assert_eq!([m.cpu_read(0x5205), m.cpu_read(0x5206)], [0x01, 0xFE]);
+    m.cpu_write(0x5205, 200);
+    m.cpu_write(0x5206, 3);
+    assert_eq!([m.cpu_read(0x5205), m.cpu_read(0x5206)], [0x58, 0x02]);
+}
+
+#[test]
+fn scanline_irq() {
+    let mut m = MMC5::new(&dump(2, 8)).unwrap();
+    m.cpu_write(0x5203, 2);
+    m.cpu_write(0x5204, 0x80);
+
+    line(&mut m);
+    assert_eq!(m.cpu_read(0x5204), 0x40);
+    line(&mut m);
+    assert!(!m.irq());
+    line(&mut m);
+    assert!(m.irq());
+
+    // Peeking leaves it pending, reading acknowledges it
+    assert_eq!(m.cpu_peek(0x5204), 0xC0);
+    assert_eq!(m.cpu_read(0x5204), 0xC0);
+    assert!(!m.irq());
+
+    // Rendering stops once the PPU goes quiet
+    (0..3).for_each(|_| m.cycle());
+    assert_eq!(m.cpu_read(0x5204), 0x00);
+}
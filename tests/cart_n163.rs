@@ -0,0 +1,127 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::cart;
+use nesmesis::cart::n163::N163;
+use nesmesis::cart::{Mapper, Mirroring};
+
+fn dump(prg: u8, chr: u8) -> Vec<u8> {
+    Dump::new(19, prg, chr).banks(0x2000, 0x0400).build()
+}
+
+// Fills the internal RAM from `a` on through the auto-incrementing port
+fn poke(m: &mut N163, a: u8, v: &[u8]) {
+    m.cpu_write(0xF800, 0x80 | a);
+    v.iter().for_each(|&v| m.cpu_write(0x4800, v));
+}
+
+#[test]
+fn banking() {
+    let mut m = cart::load(&dump(8, 4)).unwrap();
+    m.cpu_write(0xE000, 3);
+    m.cpu_write(0xE800, 5);
+    m.cpu_write(0xF000, 7);
+    let banks = [0x8000, 0xA000, 0xC000, 0xE000]
+        .iter()
+        .map(|&a| m.cpu_read(a));
+    assert_eq!(banks.collect::<Vec<_>>(), [3, 5, 7, 15]);
+
+    m.cpu_write(0x8000, 2);
+    assert_eq!(m.ppu_read(0x0000), 2);
+
+    // Pattern table bank backed by the second CIRAM page, seen through a nametable
+    m.cpu_write(0x8800, 0xE1);
+    m.cpu_write(0xC800, 0xE1);
+    m.ppu_write(0x0400, 0x42);
+    assert_eq!(m.ppu_read(0x2400), 0x42);
+
+    // Unless CIRAM is disabled for the left table
+    m.cpu_write(0xE800, 0x45);
+    assert_eq!(m.ppu_read(0x0400), 1);
+}
+
+#[test]
+fn nametables() {
+    let mut m = N163::new(&dump(8, 4)).unwrap();
+    [0xE0, 0xE1, 0xE0, 0xE1]
+        .iter()
+        .enumerate()
+        .for_each(|(i, &v)| m.cpu_write(0xC000 + i as u16 * 0x800, v));
+    assert_eq!(m.mirroring(), Mirroring::Vertical);
+
+    m.ppu_write(0x2C05, 0x42);
+    assert_eq!(m.ppu_read(0x2405), 0x42);
+
+    // A nametable straight from CHR ROM
+    m.cpu_write(0xC000, 5);
+    assert_eq!(m.mirroring(), Mirroring::MapperControlled);
+    assert_eq!(m.ppu_read(0x2000), 5);
+    m.ppu_write(0x2000, 0x43);
+    assert_eq!(m.ppu_read(0x2000), 5);
+}
+
+#[test]
+fn internal_ram_and_irq() {
+    let mut m = N163::new(&dump(8, 4)).unwrap();
+    poke(&mut m, 0x10, &[1, 2, 3]);
+
+    m.cpu_write(0xF800, 0x90);
+    assert_eq!(m.cpu_peek(0x4800), 1);
+    let v = (0..3).map(|_| m.cpu_read(0x4800)).collect::<Vec<_>>();
+    assert_eq!(v, [1, 2, 3]);
+
+    // Counts up to $7FFF and stays there
+    m.cpu_write(0x5000, 0xFD);
+    m.cpu_write(0x5800, 0xFF);
+    m.cycle();
+    assert!(!m.irq());
+    m.cycle();
+    assert!(m.irq());
+    m.cycle();
+    assert_eq!((m.cpu_read(0x5000), m.cpu_read(0x5800)), (0xFF, 0xFF));
+
+    m.cpu_write(0x5800, 0x00);
+    assert!(!m.irq());
+}
+
+#[test]
+fn prg_ram_protect() {
+    let mut m = N163::new(&dump(8, 4)).unwrap();
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0);
+
+    m.cpu_write(0xF800, 0x40);
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+
+    // One bit per 2K window
+    m.cpu_write(0xF800, 0x41);
+    m.cpu_write(0x6000, 0x43);
+    m.cpu_write(0x6800, 0x44);
+    assert_eq!((m.cpu_read(0x6000), m.cpu_read(0x6800)), (0x42, 0x44));
+}
+
+#[test]
+fn audio() {
+    let mut m = N163::new(&dump(8, 4)).unwrap();
+
+    // One channel looping over a 4 sample wave at address 0, at full volume
+    poke(&mut m, 0x00, &[0x0F, 0x00]);
+    poke(
+        &mut m,
+        0x78,
+        &[0x00, 0x00, 0x00, 0x00, 0xFC, 0x00, 0x00, 0x0F],
+    );
+    (0..15).for_each(|_| m.cycle());
+    assert!((m.audio() - 0.1494 * 105.0 / 120.0).abs() < 1e-6);
+
+    // Four channels, the other three silent
+    poke(&mut m, 0x7F, &[0x3F]);
+    (0..60).for_each(|_| m.cycle());
+    assert!((m.audio() - 0.1494 * 105.0 / 480.0).abs() < 1e-6);
+
+    m.cpu_write(0xE000, 0x40);
+    assert_eq!(m.audio(), 0.0);
+}
@@ -0,0 +1,75 @@
+extern crate nesmesis;
+
+use nesmesis::cart;
+use nesmesis::cart::{Mirroring, Nametables};
+
+const ROM: &[u8] = include_bytes!("nestest/nestest.nes");
+
+// Which of the four nametables a byte written to each of them shows up in
+fn layout(m: Mirroring) -> Vec<u8> {
+    let mut n = Nametables::new(m);
+    (0..4).for_each(|t| n.write(m, 0x2000 + t * 0x400, t as u8 + 1));
+    (0..4).map(|t| n.read(m, 0x2000 + t * 0x400)).collect()
+}
+
+#[test]
+fn mirroring() {
+    assert_eq!(layout(Mirroring::Horizontal), [2, 2, 4, 4]);
+    assert_eq!(layout(Mirroring::Vertical), [3, 4, 3, 4]);
+    assert_eq!(layout(Mirroring::SingleScreenLower), [4; 4]);
+    assert_eq!(layout(Mirroring::SingleScreenUpper), [4; 4]);
+    assert_eq!(layout(Mirroring::FourScreen), [1, 2, 3, 4]);
+
+    // Single-screen A and B are separate pages
+    let mut n = Nametables::new(Mirroring::Vertical);
+    n.write(Mirroring::SingleScreenLower, 0x2000, 0x0A);
+    n.write(Mirroring::SingleScreenUpper, 0x2000, 0x0B);
+    assert_eq!(
+        (
+            n.read(Mirroring::Vertical, 0x2000),
+            n.read(Mirroring::Vertical, 0x2400)
+        ),
+        (0x0A, 0x0B)
+    );
+}
+
+#[test]
+fn nrom_ppu_space() {
+    let mut m = cart::load(ROM).unwrap();
+    let chr = 16 + 16384;
+
+    assert_eq!(m.ppu_read(0x0000), ROM[chr]);
+    assert_eq!(m.ppu_read(0x1FFF), ROM[chr + 0x1FFF]);
+
+    // nestest is horizontally mirrored, and $3000-$3EFF mirrors the nametables
+    assert_eq!(m.mirroring(), Mirroring::Horizontal);
+    m.ppu_write(0x2405, 0x42);
+    assert_eq!(m.ppu_read(0x2005), 0x42);
+    assert_eq!(m.ppu_read(0x3005), 0x42);
+    assert_eq!(m.ppu_read(0x2805), 0x00);
+
+    // CHR ROM isn't writable
+    m.ppu_write(0x0000, !ROM[chr]);
+    assert_eq!(m.ppu_read(0x0000), ROM[chr]);
+}
+
+#[test]
+fn mapper_mirroring() {
+    // MMC1 switching between vertical and single-screen B at run time
+    let mut d = ROM.to_vec();
+    d[6] = 0x10;
+    let mut m = cart::load(&d).unwrap();
+    let control = |m: &mut Box<dyn cart::Mapper>, v: u8| {
+        (0..5).for_each(|i| {
+            m.cycle();
+            m.cycle();
+            m.cpu_write(0x8000, v >> i);
+        })
+    };
+
+    control(&mut m, 0x0E);
+    m.ppu_write(0x2400, 0x11);
+    assert_eq!((m.ppu_read(0x2C00), m.ppu_read(0x2000)), (0x11, 0x00));
+    control(&mut m, 0x0D);
+    assert_eq!((m.ppu_read(0x2000), m.ppu_read(0x2800)), (0x11, 0x11));
+}
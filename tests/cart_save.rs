@@ -0,0 +1,85 @@
+extern crate nesmesis;
+
+use std::cell::RefCell;
+
+use nesmesis::cart;
+use nesmesis::cart::error::CartError;
+use nesmesis::cart::save::{self, Autosave};
+
+const ROM: &[u8] = include_bytes!("nestest/nestest.nes");
+
+// nestest as an MMC1 board, with or without a battery
+fn dump(battery: bool) -> Vec<u8> {
+    let mut d = ROM.to_vec();
+    d[6] = 0x10 | if battery { 0x02 } else { 0x00 };
+    d
+}
+
+#[test]
+fn export_import() {
+    let mut m = cart::load(&dump(true)).unwrap();
+    m.cpu_write(0x6000, 0x12);
+    m.cpu_write(0x7FFF, 0x34);
+
+    let sav = save::export(m.as_ref()).unwrap();
+    assert_eq!((sav.len(), sav[0], sav[0x1FFF]), (0x2000, 0x12, 0x34));
+
+    let mut m = cart::load(&dump(true)).unwrap();
+    save::import(m.as_mut(), &sav).unwrap();
+    assert_eq!((m.cpu_read(0x6000), m.cpu_read(0x7FFF)), (0x12, 0x34));
+
+    assert_eq!(
+        save::import(m.as_mut(), &sav[1..]),
+        Err(CartError::SaveSize {
+            expected: 0x2000,
+            found: 0x1FFF
+        })
+    );
+}
+
+#[test]
+fn only_nvram_is_saved() {
+    // NES 2.0 with 8K of PRG RAM and 8K of PRG NVRAM above it
+    let mut d = dump(true);
+    d[7] |= 0x08;
+    d[10] = 0x77;
+
+    let mut m = cart::load(&d).unwrap();
+    m.cpu_write(0x6000, 0x12);
+
+    let sav = save::export(m.as_ref()).unwrap();
+    assert_eq!((sav.len(), sav[0]), (0x2000, 0x00));
+}
+
+#[test]
+fn no_battery() {
+    let mut m = cart::load(&dump(false)).unwrap();
+    assert!(save::export(m.as_ref()).is_none());
+    assert_eq!(
+        save::import(m.as_mut(), &[0; 0x2000]),
+        Err(CartError::NoBattery)
+    );
+}
+
+#[test]
+fn autosave() {
+    let saves = RefCell::new(Vec::new());
+    let mut m = cart::load(&dump(true)).unwrap();
+    let mut a = Autosave::new(m.as_ref(), 60, |r: &[u8]| saves.borrow_mut().push(r[0]));
+
+    // Nothing changed, nothing written
+    (0..120).for_each(|_| a.tick(m.as_ref()));
+    assert!(saves.borrow().is_empty());
+
+    m.cpu_write(0x6000, 0x01);
+    (0..59).for_each(|_| a.tick(m.as_ref()));
+    assert!(saves.borrow().is_empty());
+    a.tick(m.as_ref());
+    assert_eq!(*saves.borrow(), [0x01]);
+
+    // Flushing on exit
+    m.cpu_write(0x6000, 0x02);
+    a.flush(m.as_ref());
+    a.flush(m.as_ref());
+    assert_eq!(*saves.borrow(), [0x01, 0x02]);
+}
@@ -0,0 +1,206 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::Dump;
+use nesmesis::cart;
+use nesmesis::cart::vrc4::{Chip, VRC4};
+use nesmesis::cart::vrc6::VRC6;
+use nesmesis::cart::{Mapper, Mirroring};
+
+// NES 2.0 dump with 8K of PRG RAM
+fn dump(mapper: u8, submapper: u8, prg: u8, chr: u8) -> Vec<u8> {
+    Dump::new(mapper, prg, chr)
+        .banks(0x2000, 0x0400)
+        .nes2(submapper)
+        .byte(10, 0x07)
+        .build()
+}
+
+#[test]
+fn vrc4_wiring() {
+    let boards = [
+        (21, 1, 0xB002), // VRC4a
+        (21, 2, 0xB040), // VRC4c
+        (23, 1, 0xB001), // VRC4f
+        (23, 2, 0xB004), // VRC4e
+        (25, 1, 0xB002), // VRC4b
+        (25, 2, 0xB008), // VRC4d
+        (21, 0, 0xB040),
+        (23, 0, 0xB004),
+        (25, 0, 0xB008),
+    ];
+
+    boards.iter().for_each(|&(mapper, submapper, high)| {
+        let mut m = cart::load(&dump(mapper, submapper, 8, 4)).unwrap();
+        m.cpu_write(0xB000, 0x05);
+        m.cpu_write(high, 0x01);
+        assert_eq!(m.ppu_read(0x0000), 0x15, "{}.{}", mapper, submapper);
+    });
+}
+
+#[test]
+fn vrc2() {
+    let mut m = VRC4::new(&dump(22, 0, 8, 4)).unwrap();
+    assert_eq!(m.chip(), Chip::Vrc2);
+
+    // VRC2a ignores the lowest CHR bank bit
+    m.cpu_write(0xB000, 6);
+    m.cpu_write(0xC001, 9);
+    assert_eq!((m.ppu_read(0x0000), m.ppu_read(0x0C00)), (3, 4));
+
+    // No PRG swap mode, and only one mirroring bit
+    m.cpu_write(0x9002, 0x03);
+    assert_eq!(m.mirroring(), Mirroring::Horizontal);
+    assert_eq!(m.cpu_read(0xC000), 14);
+
+    // The $6000 latch of boards without PRG RAM
+    let mut d = dump(23, 3, 8, 4);
+    d[10] = 0;
+    let mut m = VRC4::new(&d).unwrap();
+    m.cpu_write(0x6000, 0xFF);
+    assert_eq!(m.cpu_read(0x6000), 0x01);
+}
+
+#[test]
+fn vrc4_prg() {
+    let mut m = cart::load(&dump(23, 0, 8, 4)).unwrap();
+    m.cpu_write(0x8000, 3);
+    m.cpu_write(0xA000, 5);
+    let banks = [0x8000, 0xA000, 0xC000, 0xE000]
+        .iter()
+        .map(|&a| m.cpu_read(a));
+    assert_eq!(banks.collect::<Vec<_>>(), [3, 5, 14, 15]);
+
+    m.cpu_write(0x9008, 0x02);
+    assert_eq!((m.cpu_read(0x8000), m.cpu_read(0xC000)), (14, 3));
+
+    m.cpu_write(0x9000, 0x03);
+    assert_eq!(m.mirroring(), Mirroring::SingleScreenUpper);
+}
+
+#[test]
+fn irq_cycle_mode() {
+    let mut m = VRC4::new(&dump(23, 0, 8, 4)).unwrap();
+    m.cpu_write(0xF000, 0x0D);
+    m.cpu_write(0xF004, 0x0F);
+    m.cpu_write(0xF008, 0x06);
+
+    m.cycle();
+    m.cycle();
+    assert!(!m.irq());
+    m.cycle();
+    assert!(m.irq());
+
+    // Acknowledging stops the counter unless the control write asked otherwise
+    m.cpu_write(0xF00C, 0);
+    assert!(!m.irq());
+    (0..0x200).for_each(|_| m.cycle());
+    assert!(!m.irq());
+}
+
+#[test]
+fn irq_scanline_mode() {
+    let mut m = VRC4::new(&dump(23, 0, 8, 4)).unwrap();
+    m.cpu_write(0xF000, 0x0E);
+    m.cpu_write(0xF004, 0x0F);
+    m.cpu_write(0xF008, 0x03);
+
+    // Two scanlines of 113 2/3 cycles
+    (0..227).for_each(|_| m.cycle());
+    assert!(!m.irq());
+    m.cycle();
+    assert!(m.irq());
+
+    // Still counting after the acknowledge, starting over from the latch
+    m.cpu_write(0xF00C, 0);
+    assert!(!m.irq());
+    (0..228).for_each(|_| m.cycle());
+    assert!(m.irq());
+}
+
+#[test]
+fn vrc6_banking() {
+    let mut m = cart::load(&dump(24, 0, 8, 4)).unwrap();
+    m.cpu_write(0x8000, 2);
+    m.cpu_write(0xC000, 7);
+    let banks = [0x8000, 0xA000, 0xC000, 0xE000]
+        .iter()
+        .map(|&a| m.cpu_read(a));
+    assert_eq!(banks.collect::<Vec<_>>(), [4, 5, 7, 15]);
+
+    (0..8).for_each(|i| m.cpu_write(0xD000 + (i / 4) * 0x1000 + i % 4, 10 + i as u8));
+    assert_eq!((m.ppu_read(0x0400), m.ppu_read(0x1C00)), (11, 17));
+
+    // 2K banks from R0-R3, horizontal mirroring and PRG RAM
+    m.cpu_write(0xB003, 0x85);
+    assert_eq!((m.ppu_read(0x0400), m.ppu_read(0x1C00)), (10, 13));
+    m.cpu_write(0xB003, 0xA5);
+    assert_eq!((m.ppu_read(0x0400), m.ppu_read(0x1C00)), (11, 13));
+    assert_eq!(m.mirroring(), Mirroring::Horizontal);
+    m.cpu_write(0x6000, 0x42);
+    assert_eq!(m.cpu_read(0x6000), 0x42);
+
+    // Mapper 26 swaps A0 and A1
+    let mut m = VRC6::new(&dump(26, 0, 8, 4)).unwrap();
+    m.cpu_write(0xD001, 9);
+    assert_eq!(m.ppu_read(0x0800), 9);
+}
+
+#[test]
+fn vrc6_audio() {
+    let mut m = VRC6::new(&dump(24, 0, 8, 4)).unwrap();
+    assert_eq!(m.audio(), 0.0);
+
+    // A full volume pulse matches the APU's loudest pulse
+    m.cpu_write(0x9000, 0x8F);
+    m.cpu_write(0x9002, 0x80);
+    assert!((m.audio() - 0.1494).abs() < 1e-6);
+    m.cpu_write(0x9002, 0x00);
+
+    // Saw adding 8 every other clock, cleared after the 14th
+    m.cpu_write(0xB000, 0x08);
+    m.cpu_write(0xB002, 0x80);
+    let unit = 0.1494 / 15.0;
+    (0..12).for_each(|_| m.cycle());
+    assert!((m.audio() - 6.0 * unit).abs() < 1e-6);
+    (0..2).for_each(|_| m.cycle());
+    assert_eq!(m.audio(), 0.0);
+
+    // Halted channels keep their output
+    (0..2).for_each(|_| m.cycle());
+    m.cpu_write(0x9003, 0x01);
+    (0..4).for_each(|_| m.cycle());
+    assert!((m.audio() - unit).abs() < 1e-6);
+}
+
+#[test]
+fn vrc7() {
+    // VRC7a selects the second register of a pair with A4
+    let mut m = cart::load(&dump(85, 2, 8, 4)).unwrap();
+    m.cpu_write(0x8000, 1);
+    m.cpu_write(0x8010, 2);
+    m.cpu_write(0x9000, 3);
+    let banks = [0x8000, 0xA000, 0xC000, 0xE000]
+        .iter()
+        .map(|&a| m.cpu_read(a));
+    assert_eq!(banks.collect::<Vec<_>>(), [1, 2, 3, 15]);
+
+    m.cpu_write(0xD000, 5);
+    m.cpu_write(0xD010, 6);
+    assert_eq!((m.ppu_read(0x1800), m.ppu_read(0x1C00)), (5, 6));
+
+    // VRC7b with A3
+    let mut m = cart::load(&dump(85, 1, 8, 4)).unwrap();
+    m.cpu_write(0x8008, 2);
+    assert_eq!(m.cpu_read(0xA000), 2);
+
+    m.cpu_write(0x9010, 0x20);
+    m.cpu_write(0x9030, 0x44);
+    assert_eq!(m.audio_registers().unwrap()[0x20], 0x44);
+
+    // Held in reset
+    m.cpu_write(0xE000, 0x40);
+    m.cpu_write(0x9030, 0x45);
+    assert_eq!(m.audio_registers().unwrap()[0x20], 0x00);
+}
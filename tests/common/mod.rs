@@ -0,0 +1,102 @@
+// Each test crate only uses part of this
+#![allow(dead_code)]
+
+use nesmesis::MMU;
+
+// #region FlatMemory Struct
+// Zeroed RAM below $8000, NOPs above. The program starts at $8000, the NMI handler
+// at $9000 and the IRQ handler at $9100 are both a single RTI.
+pub struct FlatMemory {
+    mem: Vec<u8>,
+}
+
+impl FlatMemory {
+    pub fn new(program: &[u8]) -> FlatMemory {
+        let mut mem = vec![0; 0x10000];
+        mem[0x8000..].iter_mut().for_each(|b| *b = 0xEA);
+        mem[0x8000..0x8000 + program.len()].copy_from_slice(program);
+        mem[0x9000] = 0x40;
+        mem[0x9100] = 0x40;
+        mem[0xFFFA..].copy_from_slice(&[0x00, 0x90, 0x00, 0x80, 0x00, 0x91]);
+        FlatMemory { mem }
+    }
+}
+
+impl MMU for FlatMemory {
+    fn read(&mut self, a: u16) -> u8 {
+        self.peek(a)
+    }
+
+    fn write(&mut self, a: u16, v: u8) {
+        self.mem[a as usize] = v
+    }
+
+    fn cycle(&mut self) {}
+
+    fn peek(&self, a: u16) -> u8 {
+        self.mem[a as usize]
+    }
+}
+// #endregion
+
+// #region Dump Builder
+// iNES dump whose PRG and CHR are cut into banks filled with their own number. `prg` and
+// `chr` count 16K and 8K units like the header, the banks default to the same sizes.
+pub struct Dump {
+    header: [u8; 16],
+    prg_bank: usize,
+    chr_bank: usize,
+}
+
+impl Dump {
+    pub fn new(mapper: u8, prg: u8, chr: u8) -> Dump {
+        let mut header = [0; 16];
+        header[..4].copy_from_slice(b"NES\x1A");
+        header[4] = prg;
+        header[5] = chr;
+        header[6] = (mapper & 0x0F) << 4;
+        header[7] = mapper & 0xF0;
+
+        Dump {
+            header,
+            prg_bank: 0x4000,
+            chr_bank: 0x2000,
+        }
+    }
+
+    pub fn banks(mut self, prg: usize, chr: usize) -> Dump {
+        self.prg_bank = prg;
+        self.chr_bank = chr;
+        self
+    }
+
+    // Mirroring, battery and trainer bits of flags 6
+    pub fn flags(mut self, f: u8) -> Dump {
+        self.header[6] |= f;
+        self
+    }
+
+    // Turns the header into NES 2.0
+    pub fn nes2(mut self, submapper: u8) -> Dump {
+        self.header[7] |= 0x08;
+        self.header[8] = submapper << 4;
+        self
+    }
+
+    // Header byte `i`, from 8 on
+    pub fn byte(mut self, i: usize, v: u8) -> Dump {
+        self.header[i] = v;
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let prg = usize::from(self.header[4]) * 0x4000;
+        let chr = usize::from(self.header[5]) * 0x2000;
+
+        let mut d = self.header.to_vec();
+        (0..prg / self.prg_bank).for_each(|b| d.extend(vec![b as u8; self.prg_bank]));
+        (0..chr / self.chr_bank).for_each(|b| d.extend(vec![b as u8; self.chr_bank]));
+        d
+    }
+}
+// #endregion
@@ -0,0 +1,190 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::FlatMemory;
+use nesmesis::cpu::CPU;
+use nesmesis::MMU;
+
+use self::Access::*;
+
+#[derive(Debug, PartialEq)]
+enum Access {
+    R(u16),
+    W(u16, u8),
+}
+
+// #region RecordingMemory Struct
+struct RecordingMemory {
+    mem: FlatMemory,
+    log: Vec<Access>,
+}
+
+impl MMU for RecordingMemory {
+    fn read(&mut self, a: u16) -> u8 {
+        self.log.push(R(a));
+        self.mem.read(a)
+    }
+
+    fn write(&mut self, a: u16, v: u8) {
+        self.log.push(W(a, v));
+        self.mem.write(a, v)
+    }
+
+    fn cycle(&mut self) {}
+
+    fn peek(&self, a: u16) -> u8 {
+        self.mem.peek(a)
+    }
+}
+// #endregion
+
+// Runs `LDX #$20; LDY #$20` and the program, then returns the bus accesses of its last
+// instruction. Every cycle is exactly one access, so that's the last `cycles` entries.
+fn accesses(program: &[u8], ram: &[(u16, u8)]) -> Vec<Access> {
+    let mut code = vec![0xA2, 0x20, 0xA0, 0x20];
+    code.extend_from_slice(program);
+
+    let mut m = RecordingMemory {
+        mem: FlatMemory::new(&code),
+        log: vec![],
+    };
+    ram.iter().for_each(|&(a, v)| m.mem.write(a, v));
+
+    let cycles = {
+        let mut c = CPU::new(&mut m);
+        c.init();
+        let mut cycles = 0;
+        while (0x8000..0x8000 + code.len() as u16).contains(&c.reg.read_pc()) {
+            cycles = c.execute().unwrap();
+        }
+        cycles as usize
+    };
+
+    let n = m.log.len();
+    m.log.split_off(n - cycles)
+}
+
+#[test]
+fn implied_reads_next_byte() {
+    assert_eq!(accesses(&[0x18], &[]), vec![R(0x8004), R(0x8005)]);
+    assert_eq!(accesses(&[0x0A], &[]), vec![R(0x8004), R(0x8005)]);
+}
+
+#[test]
+fn absolute_indexed_read() {
+    // LDA $1200,X then LDA $12F0,X
+    assert_eq!(
+        accesses(&[0xBD, 0x00, 0x12], &[]),
+        vec![R(0x8004), R(0x8005), R(0x8006), R(0x1220)]
+    );
+    assert_eq!(
+        accesses(&[0xBD, 0xF0, 0x12], &[]),
+        vec![R(0x8004), R(0x8005), R(0x8006), R(0x1210), R(0x1310)]
+    );
+}
+
+#[test]
+fn absolute_indexed_write() {
+    // STA $1200,X
+    assert_eq!(
+        accesses(&[0xA9, 0x55, 0x9D, 0x00, 0x12], &[]),
+        vec![R(0x8006), R(0x8007), R(0x8008), R(0x1220), W(0x1220, 0x55)]
+    );
+}
+
+#[test]
+fn read_modify_write_writes_twice() {
+    // INC $10, DEC $10F0,X
+    assert_eq!(
+        accesses(&[0xE6, 0x10], &[(0x10, 0x41)]),
+        vec![
+            R(0x8004),
+            R(0x8005),
+            R(0x0010),
+            W(0x0010, 0x41),
+            W(0x0010, 0x42)
+        ]
+    );
+    assert_eq!(
+        accesses(&[0xDE, 0xF0, 0x10], &[(0x1110, 0x41)]),
+        vec![
+            R(0x8004),
+            R(0x8005),
+            R(0x8006),
+            R(0x1010),
+            R(0x1110),
+            W(0x1110, 0x41),
+            W(0x1110, 0x40),
+        ]
+    );
+}
+
+#[test]
+fn zero_page_indexed() {
+    // LDA $F0,X wraps in the zero page after a dummy read of the base address
+    assert_eq!(
+        accesses(&[0xB5, 0xF0], &[]),
+        vec![R(0x8004), R(0x8005), R(0x00F0), R(0x0010)]
+    );
+}
+
+#[test]
+fn indirect_modes() {
+    // LDA ($F0,X) then LDA ($10),Y crossing a page
+    assert_eq!(
+        accesses(&[0xA1, 0xF0], &[(0x10, 0x34), (0x11, 0x12)]),
+        vec![
+            R(0x8004),
+            R(0x8005),
+            R(0x00F0),
+            R(0x0010),
+            R(0x0011),
+            R(0x1234)
+        ]
+    );
+    assert_eq!(
+        accesses(&[0xB1, 0x10], &[(0x10, 0xF0), (0x11, 0x12)]),
+        vec![
+            R(0x8004),
+            R(0x8005),
+            R(0x0010),
+            R(0x0011),
+            R(0x1210),
+            R(0x1310)
+        ]
+    );
+}
+
+#[test]
+fn jsr_and_rts() {
+    // JSR $8010, with an RTS there
+    let mut code = vec![0x20, 0x10, 0x80];
+    code.resize(0x0C, 0xEA);
+    code.push(0x60);
+
+    assert_eq!(
+        accesses(&code[..3], &[]),
+        vec![
+            R(0x8004),
+            R(0x8005),
+            R(0x01FD),
+            W(0x01FD, 0x80),
+            W(0x01FC, 0x06),
+            R(0x8006)
+        ]
+    );
+    assert_eq!(
+        accesses(&[0x08, 0x28], &[]),
+        vec![R(0x8005), R(0x8006), R(0x01FC), R(0x01FD)]
+    );
+}
+
+#[test]
+fn taken_branch_crossing_page() {
+    // BNE -$10 from $8006 lands on $7FF6 after a read at $80F6
+    assert_eq!(
+        accesses(&[0xD0, 0xF0], &[]),
+        vec![R(0x8004), R(0x8005), R(0x8006), R(0x80F6)]
+    );
+}
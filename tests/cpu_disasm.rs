@@ -0,0 +1,87 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::FlatMemory;
+use nesmesis::cpu::disasm::{disassemble, disassemble_at};
+use nesmesis::cpu::CPU;
+
+fn text(d: &[u8], address: u16) -> String {
+    disassemble(d, address).unwrap().to_string()
+}
+
+#[test]
+fn operand_syntax() {
+    assert_eq!(text(&[0xEA], 0x8000), "NOP");
+    assert_eq!(text(&[0x0A], 0x8000), "ASL A");
+    assert_eq!(text(&[0xA9, 0x10], 0x8000), "LDA #$10");
+    assert_eq!(text(&[0xA5, 0x10], 0x8000), "LDA $10");
+    assert_eq!(text(&[0xB5, 0x10], 0x8000), "LDA $10,X");
+    assert_eq!(text(&[0xB6, 0x10], 0x8000), "LDX $10,Y");
+    assert_eq!(text(&[0xAD, 0x34, 0x12], 0x8000), "LDA $1234");
+    assert_eq!(text(&[0xBD, 0x34, 0x12], 0x8000), "LDA $1234,X");
+    assert_eq!(text(&[0xB9, 0x34, 0x12], 0x8000), "LDA $1234,Y");
+    assert_eq!(text(&[0xA1, 0x10], 0x8000), "LDA ($10,X)");
+    assert_eq!(text(&[0xB1, 0x10], 0x8000), "LDA ($10),Y");
+    assert_eq!(text(&[0x6C, 0x34, 0x12], 0x8000), "JMP ($1234)");
+    assert_eq!(text(&[0x20, 0x34, 0x12], 0x8000), "JSR $1234");
+}
+
+#[test]
+fn relative_targets() {
+    assert_eq!(text(&[0xD0, 0x04], 0xC72F), "BNE $C735");
+    assert_eq!(text(&[0x10, 0xFE], 0xC000), "BPL $C000");
+    assert_eq!(text(&[0x30, 0x80], 0x8000), "BMI $7F82");
+}
+
+#[test]
+fn unofficial_opcodes() {
+    let i = disassemble(&[0x04, 0xA9], 0xC6BD).unwrap();
+    assert!(!i.official);
+    assert_eq!(i.to_string(), "*NOP $A9");
+    assert_eq!(text(&[0xEB, 0x01], 0x8000), "*SBC #$01");
+    assert_eq!(text(&[0xA3, 0x40], 0x8000), "*LAX ($40,X)");
+    assert_eq!(text(&[0x02], 0x8000), "*KIL");
+}
+
+#[test]
+fn lengths() {
+    let i = disassemble(&[0x8D, 0x00, 0x02, 0xFF], 0x8000).unwrap();
+    assert_eq!(i.len(), 3);
+    assert_eq!(i.bytes, vec![0x8D, 0x00, 0x02]);
+    assert_eq!(i.next(), 0x8003);
+
+    assert_eq!(disassemble(&[0xE8, 0x00], 0x8000).unwrap().len(), 1);
+    assert_eq!(disassemble(&[0xF0, 0x00], 0x8000).unwrap().len(), 2);
+    assert!(disassemble(&[0x8D, 0x00], 0x8000).is_none());
+    assert!(disassemble(&[], 0x8000).is_none());
+}
+
+#[test]
+fn from_memory() {
+    let m = FlatMemory::new(&[0xA2, 0x01, 0x4C, 0x00, 0x80]);
+    let first = disassemble_at(&m, 0x8000);
+    let second = disassemble_at(&m, first.next());
+
+    assert_eq!(first.to_string(), "LDX #$01");
+    assert_eq!(second.address, 0x8002);
+    assert_eq!(second.to_string(), "JMP $8000");
+}
+
+#[test]
+fn trace_wraps_ppu_position_per_frame() {
+    // JMP $8000 takes 3 cycles, 9 dots
+    let mut c = CPU::new(FlatMemory::new(&[0x4C, 0x00, 0x80]));
+    c.init();
+
+    // Into the second frame, which is one dot short
+    (0..9926).for_each(|_| {
+        c.execute().unwrap();
+    });
+    assert!(c.trace().ends_with("PPU:  0, 13 CYC:29785"));
+
+    (0..9930).for_each(|_| {
+        c.execute().unwrap();
+    });
+    assert!(c.trace().ends_with("PPU:  0, 42 CYC:59575"));
+}
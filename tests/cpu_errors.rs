@@ -0,0 +1,192 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::FlatMemory;
+use nesmesis::bus::Bus;
+use nesmesis::cart::{Mapper, Mirroring};
+use nesmesis::cpu::error::CpuError;
+use nesmesis::cpu::{KillPolicy, CPU};
+use nesmesis::MMU;
+
+// #region FaultyMemory Struct
+// Faults on any access to $4020-$5FFF, where no cartridge answers
+pub struct FaultyMemory {
+    mem: FlatMemory,
+    fault: Option<u16>,
+}
+
+impl MMU for FaultyMemory {
+    fn read(&mut self, a: u16) -> u8 {
+        if let 0x4020..=0x5FFF = a {
+            self.fault = Some(a)
+        }
+        self.mem.read(a)
+    }
+
+    fn write(&mut self, a: u16, v: u8) {
+        match a {
+            0x4020..=0x5FFF => self.fault = Some(a),
+            _ => self.mem.write(a, v),
+        }
+    }
+
+    fn cycle(&mut self) {}
+
+    fn peek(&self, a: u16) -> u8 {
+        self.mem.peek(a)
+    }
+
+    fn fault(&mut self) -> Option<u16> {
+        self.fault.take()
+    }
+}
+// #endregion
+
+// #region FaultyCart Struct
+// 32K of PRG ROM, faulting on any access to $4020-$7FFF
+struct FaultyCart {
+    prg_rom: Vec<u8>,
+    fault: Option<u16>,
+}
+
+impl FaultyCart {
+    fn new(program: &[u8]) -> FaultyCart {
+        let mut prg_rom = vec![0xEA; 0x8000];
+        prg_rom[..program.len()].copy_from_slice(program);
+        prg_rom[0x7FFC..0x7FFE].copy_from_slice(&[0x00, 0x80]);
+        FaultyCart { prg_rom, fault: None }
+    }
+}
+
+impl Mapper for FaultyCart {
+    fn cpu_peek(&self, a: u16) -> u8 {
+        match a {
+            0x8000..=0xFFFF => self.prg_rom[a as usize - 0x8000],
+            _ => 0,
+        }
+    }
+
+    fn cpu_read(&mut self, a: u16) -> u8 {
+        if let 0x4020..=0x7FFF = a {
+            self.fault = Some(a)
+        }
+        self.cpu_peek(a)
+    }
+
+    fn cpu_write(&mut self, a: u16, _: u8) {
+        if let 0x4020..=0x7FFF = a {
+            self.fault = Some(a)
+        }
+    }
+
+    fn fault(&mut self) -> Option<u16> {
+        self.fault.take()
+    }
+
+    fn ppu_read(&mut self, _: u16) -> u8 {
+        0
+    }
+
+    fn ppu_write(&mut self, _: u16, _: u8) {}
+
+    fn cycle(&mut self) {}
+
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::Vertical
+    }
+}
+// #endregion
+
+#[test]
+fn kill_reports_error_by_default() {
+    let mut c = CPU::new(FlatMemory::new(&[0xEA, 0x02]));
+    c.init();
+
+    c.execute().unwrap();
+    assert_eq!(c.execute(), Err(CpuError::Jammed { pc: 0x8001, opcode: 0x02 }));
+    assert_eq!(c.reg.read_pc(), 0x8001);
+}
+
+#[test]
+fn kill_halts_until_reset() {
+    let mut c = CPU::new(FlatMemory::new(&[0x12]));
+    c.set_kill_policy(KillPolicy::Halt);
+    c.init();
+
+    c.execute().unwrap();
+    assert!(c.jammed());
+
+    // Not even an NMI gets through
+    c.trigger_nmi();
+    (0..4).for_each(|_| assert_eq!(c.execute(), Ok(1)));
+    assert_eq!(c.reg.read_pc(), 0x8001);
+
+    c.init();
+    assert!(!c.jammed());
+    assert_eq!(c.reg.read_pc(), 0x8000);
+}
+
+#[test]
+fn kill_as_nop() {
+    let mut c = CPU::new(FlatMemory::new(&[0xF2]));
+    c.set_kill_policy(KillPolicy::Nop);
+    c.init();
+
+    assert_eq!(c.execute(), Ok(2));
+    assert_eq!(c.reg.read_pc(), 0x8001);
+}
+
+#[test]
+fn unstable_opcode() {
+    let mut c = CPU::new(FlatMemory::new(&[0x8B, 0x00]));
+    c.init();
+
+    assert_eq!(c.execute(), Err(CpuError::Unstable { pc: 0x8000, opcode: 0x8B }));
+}
+
+#[test]
+fn bus_fault() {
+    // STA $5000
+    let m = FaultyMemory {
+        mem: FlatMemory::new(&[0x8D, 0x00, 0x50]),
+        fault: None,
+    };
+    let mut c = CPU::new(m);
+    c.init();
+
+    let e = c.execute().unwrap_err();
+    assert_eq!(e, CpuError::BusFault { pc: 0x8000, address: 0x5000 });
+    assert_eq!(e.to_string(), "Bus fault accessing 5000 from instruction at 8000");
+    assert_eq!(c.execute(), Ok(2));
+}
+
+#[test]
+fn bus_fault_before_kill_and_unstable() {
+    // JMP $5000, to opcodes fetched from where nothing answers
+    let m = FaultyMemory {
+        mem: FlatMemory::new(&[0x4C, 0x00, 0x50]),
+        fault: None,
+    };
+    let mut c = CPU::new(m);
+    c.bus.mem.write(0x5000, 0x02);
+    c.init();
+
+    c.execute().unwrap();
+    assert_eq!(c.execute(), Err(CpuError::BusFault { pc: 0x5000, address: 0x5000 }));
+
+    c.bus.mem.write(0x5000, 0x8B);
+    assert_eq!(c.execute(), Err(CpuError::BusFault { pc: 0x5000, address: 0x5000 }));
+}
+
+#[test]
+fn bus_fault_from_the_cart() {
+    // LDA $6000, STA $5000
+    let cart = FaultyCart::new(&[0xAD, 0x00, 0x60, 0x8D, 0x00, 0x50]);
+    let mut c = CPU::new(Bus::new(Box::new(cart)));
+    c.init();
+
+    assert_eq!(c.execute(), Err(CpuError::BusFault { pc: 0x8000, address: 0x6000 }));
+    assert_eq!(c.execute(), Err(CpuError::BusFault { pc: 0x8003, address: 0x5000 }));
+    assert_eq!(c.execute(), Ok(2));
+}
@@ -0,0 +1,240 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::FlatMemory;
+use nesmesis::cpu::reg::Register;
+use nesmesis::cpu::CPU;
+use nesmesis::MMU;
+
+// #region LineMemory Struct
+// FlatMemory raising the NMI or IRQ line from a given bus cycle on. The reset sequence
+// takes cycles 1 to 7, so the first instruction starts on cycle 8.
+struct LineMemory {
+    mem: FlatMemory,
+    cycles: u64,
+    nmi_at: Option<u64>,
+    irq_at: Option<u64>,
+}
+
+impl LineMemory {
+    fn new(program: &[u8], nmi_at: Option<u64>, irq_at: Option<u64>) -> LineMemory {
+        LineMemory {
+            mem: FlatMemory::new(program),
+            cycles: 0,
+            nmi_at,
+            irq_at,
+        }
+    }
+}
+
+impl MMU for LineMemory {
+    fn read(&mut self, a: u16) -> u8 {
+        self.mem.read(a)
+    }
+
+    fn write(&mut self, a: u16, v: u8) {
+        self.mem.write(a, v)
+    }
+
+    fn cycle(&mut self) {
+        self.cycles += 1;
+    }
+
+    fn peek(&self, a: u16) -> u8 {
+        self.mem.peek(a)
+    }
+
+    fn nmi(&self) -> bool {
+        self.nmi_at.is_some_and(|c| self.cycles >= c)
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_at.is_some_and(|c| self.cycles >= c)
+    }
+}
+// #endregion
+
+#[test]
+fn nmi_is_edge_triggered() {
+    let mut c = CPU::new(FlatMemory::new(&[]));
+    c.init();
+
+    c.trigger_nmi();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8001);
+
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9000);
+    assert_eq!(c.reg.read(Register::SP), 0xFA);
+    assert_eq!(c.bus.read(0x01FB), 0x24);
+    assert_eq!(c.bus.read(0x01FC), 0x01);
+    assert_eq!(c.bus.read(0x01FD), 0x80);
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8002);
+}
+
+#[test]
+fn irq_is_masked_by_interrupt_flag() {
+    let mut c = CPU::new(FlatMemory::new(&[]));
+    c.init();
+
+    c.assert_irq();
+    (0..4).for_each(|_| {
+        c.execute().unwrap();
+    });
+    assert_eq!(c.reg.read_pc(), 0x8004);
+}
+
+#[test]
+fn irq_is_level_triggered() {
+    // CLI
+    let mut c = CPU::new(FlatMemory::new(&[0x58]));
+    c.init();
+
+    c.assert_irq();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+    assert_eq!(c.bus.read(0x01FB) & 0x14, 0x00);
+
+    // RTI returns with I clear, line still held so the IRQ is taken again
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8002);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+
+    c.release_irq();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8004);
+}
+
+#[test]
+fn cli_sei_delay_by_one_instruction() {
+    // CLI, NOP, SEI, NOP
+    let mut c = CPU::new(FlatMemory::new(&[0x58, 0xEA, 0x78, 0xEA]));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+
+    // SEI still lets the IRQ through right after it
+    c.assert_irq();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8003);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+}
+
+#[test]
+fn irq_polled_on_second_to_last_cycle() {
+    // CLI on cycles 8-9, then NOPs on 10-11 and 12-13
+    let mut c = CPU::new(LineMemory::new(&[0x58], None, Some(10)));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+
+    // Asserted during the last cycle of the first NOP, so the second one still runs
+    let mut c = CPU::new(LineMemory::new(&[0x58], None, Some(11)));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8003);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+}
+
+#[test]
+fn taken_branch_delays_irq() {
+    // CLI, then LDA $00 on cycles 10-12 polls the IRQ raised on cycle 11
+    let mut c = CPU::new(LineMemory::new(&[0x58, 0xA5, 0x00], None, Some(11)));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+
+    // Same timing with BNE +0, which only lets the IRQ in after the next instruction
+    let mut c = CPU::new(LineMemory::new(&[0x58, 0xD0, 0x00], None, Some(11)));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8004);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+}
+
+#[test]
+fn taken_branch_delays_nmi() {
+    // LDA $00 on cycles 8-10 polls the NMI raised on cycle 9
+    let mut c = CPU::new(LineMemory::new(&[0xA5, 0x00], Some(9), None));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9000);
+
+    // BNE +0 in the same page holds it back until after the next instruction
+    let mut c = CPU::new(LineMemory::new(&[0xD0, 0x00], Some(9), None));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8003);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9000);
+}
+
+#[test]
+fn nmi_hijacks_brk() {
+    // BRK pushes P on cycle 12, an NMI latched before that takes over its vector
+    let mut c = CPU::new(LineMemory::new(&[0x00, 0x00], Some(11), None));
+    c.init();
+
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9000);
+    assert_eq!(c.bus.read(0x01FB), 0x34);
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8003);
+
+    // One cycle later BRK completes, and the NMI waits for the handler's first instruction
+    let mut c = CPU::new(LineMemory::new(&[0x00, 0x00], Some(12), None));
+    c.init();
+
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9100);
+
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x8002);
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9000);
+    assert_eq!(c.bus.read(0x01FB), 0x24);
+}
+
+#[test]
+fn nmi_hijacks_irq() {
+    // CLI, NOP, IRQ sequence on cycles 12-18, with the NMI raised before the P push
+    let mut c = CPU::new(LineMemory::new(&[0x58], Some(15), Some(10)));
+    c.init();
+
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.reg.read_pc(), 0x9000);
+    assert_eq!(c.bus.read(0x01FB), 0x20);
+}
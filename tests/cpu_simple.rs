@@ -1,84 +1,35 @@
 extern crate nesmesis;
 
-use nesmesis::cart::nrom::NROM;
-use nesmesis::cart::Mapper;
-use nesmesis::cpu::reg::Register;
+use nesmesis::bus::Bus;
+use nesmesis::cart;
 use nesmesis::cpu::CPU;
 use nesmesis::MMU;
 
+use std::fs;
 use std::io::BufRead;
 use std::io::BufReader;
 
-// #region TestMemory Struct
-pub struct TestMemory {
-    ram: [u8; 0x800],
-    rom: NROM,
-}
-
-impl TestMemory {
-    pub fn new(d: &[u8]) -> TestMemory {
-        TestMemory {
-            ram: [0; 0x800],
-            rom: NROM::new(d),
-        }
-    }
-}
-
-impl MMU for TestMemory {
-    fn read(&self, a: u16) -> u8 {
-        match a {
-            0x0000...0x1FFF => self.ram[a as usize % 0x800],
-            _ => self.rom.cpu_read(a),
-        }
-    }
-
-    fn write(&mut self, a: u16, v: u8) {
-        match a {
-            0x0000...0x1FFF => self.ram[a as usize % 0x800] = v,
-            _ => self.rom.cpu_write(a, v),
-        }
-    }
-
-    fn cycle(&mut self) {}
-}
-// #endregion
-
 // #region NESTEST
 const ROM: &[u8] = include_bytes!("nestest/nestest.nes");
 const LOG: &[u8] = include_bytes!("nestest/log");
 
 #[test]
 fn cpu_nestest() {
-    use self::Register::*;
-
-    let mut r = TestMemory::new(ROM);
-    let mut c = CPU::new(&mut r);
+    let mut c = CPU::new(Bus::new(cart::load(ROM).unwrap()));
     c.init();
     c.reg.write_pc(0xC000);
 
     let r = BufReader::new(LOG);
 
-    r.lines().into_iter().for_each(|line| {
-        assert_eq!(
-            line.unwrap(),
-            format!(
-                "{:04X} A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X}",
-                c.reg.read_pc(),
-                c.reg.read(A),
-                c.reg.read(X),
-                c.reg.read(Y),
-                c.reg.read(P),
-                c.reg.read(SP)
-            )
-        );
-
-        let _ = c.execute().unwrap();
+    r.lines().for_each(|line| {
+        assert_eq!(line.unwrap(), c.trace());
+        c.execute().unwrap();
     })
 }
 // #endregion
 
 // #region Single Instructions Tests
-const INSTRUCTIONS_SINGLES: [(&[u8], &'static str); 0x10] = [
+const INSTRUCTIONS_SINGLES: [(&[u8], &str); 0x10] = [
     (include_bytes!("ins/01-basics.nes"), "01-basics"),
     (include_bytes!("ins/02-implied.nes"), "02-implied"),
     (include_bytes!("ins/03-immediate.nes"), "03-immediate"),
@@ -97,13 +48,16 @@ const INSTRUCTIONS_SINGLES: [(&[u8], &'static str); 0x10] = [
     (include_bytes!("ins/16-special.nes"), "16-special"),
 ];
 
+// About 56 seconds of NES time, several times what the slowest ROM needs
+const CYCLE_LIMIT: u64 = 100_000_000;
+
 fn cpu_instruction_test(x: &[u8], s: &str) -> String {
-    let mut a = TestMemory::new(x);
-    let mut c = CPU::new(&mut a);
+    let mut c = CPU::new(Bus::new(cart::load(x).unwrap()));
     c.init();
 
     loop {
-        let _ = c.execute().unwrap();
+        assert!(c.total_cycles() < CYCLE_LIMIT, "{} never reported a result", s);
+        c.execute().unwrap();
         let mut x = 0u8;
         if c.bus.read(0x6001) == 0xDE && c.bus.read(0x6002) == 0xB0 && c.bus.read(0x6003) == 0x61 {
             let mut vec = vec![];
@@ -133,3 +87,26 @@ fn cpu_instructions_test() {
         .for_each(|&(a, b)| assert_eq!(cpu_instruction_test(a, b), format!("{}  Passed", b)))
 }
 // #endregion
+
+// #region Interrupt Tests
+// blargg's cpu_interrupts_v2 singles aren't vendored, they're read from tests/interrupts.
+// They also time interrupts off the PPU's NMI and the APU frame IRQ, which the bus
+// doesn't have yet.
+const INTERRUPTS_SINGLES: [&str; 5] = [
+    "1-cli_latency",
+    "2-nmi_and_brk",
+    "3-nmi_and_irq",
+    "4-irq_and_dma",
+    "5-branch_delays_irq",
+];
+
+#[test]
+#[ignore = "needs the cpu_interrupts_v2 ROMs in tests/interrupts and a PPU and APU"]
+fn cpu_interrupts_test() {
+    INTERRUPTS_SINGLES.iter().for_each(|&s| {
+        let path = format!("{}/tests/interrupts/{}.nes", env!("CARGO_MANIFEST_DIR"), s);
+        let rom = fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        assert_eq!(cpu_instruction_test(&rom, s), format!("{}  Passed", s))
+    })
+}
+// #endregion
@@ -0,0 +1,66 @@
+extern crate nesmesis;
+
+use nesmesis::cpu::ops::{AddressingMode, Operation};
+use nesmesis::cpu::table::{Access, Mode, OPCODES};
+
+#[test]
+fn table_matches_decoder() {
+    (0..0x100usize).for_each(|op| {
+        let info = OPCODES[op];
+        let ins = Operation::from(op as u8);
+
+        let mode = match (ins, ins.mode()) {
+            (Operation::Branch(..), _) => Mode::Relative,
+            (Operation::Jump(None), _) => Mode::Absolute,
+            (Operation::Asl(None), _)
+            | (Operation::Lsr(None), _)
+            | (Operation::Rol(None), _)
+            | (Operation::Ror(None), _) => Mode::Accumulator,
+            (_, None) => Mode::Implied,
+            (_, Some(AddressingMode::Immediate)) => Mode::Immediate,
+            (_, Some(AddressingMode::ZeroPage)) => Mode::ZeroPage,
+            (_, Some(AddressingMode::ZeroPageX)) => Mode::ZeroPageX,
+            (_, Some(AddressingMode::ZeroPageY)) => Mode::ZeroPageY,
+            (_, Some(AddressingMode::Absolute)) => Mode::Absolute,
+            (_, Some(AddressingMode::AbsoluteX(_))) => Mode::AbsoluteX,
+            (_, Some(AddressingMode::AbsoluteY(_))) => Mode::AbsoluteY,
+            (_, Some(AddressingMode::Indirect)) => Mode::Indirect,
+            (_, Some(AddressingMode::IndirectX)) => Mode::IndirectX,
+            (_, Some(AddressingMode::IndirectY(_))) => Mode::IndirectY,
+        };
+
+        if ins != Operation::Unstable {
+            assert_eq!(info.mode, mode, "opcode {:02X}", op);
+        }
+
+        // The decoder's flag means "penalty only on page cross", writes always pay it
+        match ins.mode() {
+            Some(AddressingMode::AbsoluteX(s))
+            | Some(AddressingMode::AbsoluteY(s))
+            | Some(AddressingMode::IndirectY(s)) => {
+                assert_eq!(info.page_cross, s, "opcode {:02X}", op);
+                assert_eq!(info.access == Access::Read, s, "opcode {:02X}", op);
+            }
+            _ => {}
+        }
+    })
+}
+
+#[test]
+fn lengths_follow_modes() {
+    OPCODES
+        .iter()
+        .for_each(|info| assert_eq!(info.length, info.mode.length()));
+    assert_eq!(OPCODES[0x20].length, 3);
+    assert_eq!(OPCODES[0x60].length, 1);
+    assert_eq!(OPCODES[0xF0].length, 2);
+}
+
+#[test]
+fn official_set() {
+    assert_eq!(OPCODES.iter().filter(|i| i.official).count(), 151);
+    assert!(OPCODES[0xEA].official);
+    assert!(!OPCODES[0x1A].official);
+    assert!(!OPCODES[0xEB].official);
+    assert_eq!(OPCODES[0xEB].mnemonic, "SBC");
+}
@@ -0,0 +1,76 @@
+extern crate nesmesis;
+
+mod common;
+
+use common::FlatMemory;
+use nesmesis::cpu::ops::Operation;
+use nesmesis::cpu::table::{Mode, OPCODES};
+use nesmesis::cpu::CPU;
+use nesmesis::MMU;
+
+// Everything but the opcodes that jam or aren't emulated
+fn timed() -> Vec<u8> {
+    (0..0x100u16)
+        .map(|op| op as u8)
+        .filter(|&op| !matches!(Operation::from(op), Operation::Kill | Operation::Unstable))
+        .collect()
+}
+
+// Runs `LDX #1; LDY #1; op lo, $00` and returns the cycles taken by `op`
+fn cycles(op: u8, lo: u8) -> u8 {
+    let mut m = FlatMemory::new(&[0xA2, 0x01, 0xA0, 0x01, op, lo, 0x00]);
+    m.write(0x00FF, 0xFF);
+    let mut c = CPU::new(m);
+    c.init();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    c.execute().unwrap()
+}
+
+#[test]
+fn reset_takes_seven_cycles() {
+    let mut c = CPU::new(FlatMemory::new(&[]));
+    c.init();
+    assert_eq!(c.total_cycles(), 7);
+}
+
+#[test]
+fn base_cycles() {
+    timed().into_iter().for_each(|op| {
+        // Branches are taken when their flag is clear, P is $24 after LDY #1
+        let taken = OPCODES[op as usize].mode == Mode::Relative && op & 0x20 == 0;
+        let expected = OPCODES[op as usize].cycles + if taken { 1 } else { 0 };
+        assert_eq!(cycles(op, 0x00), expected, "opcode {:02X}", op);
+    })
+}
+
+#[test]
+fn page_cross_cycles() {
+    timed()
+        .into_iter()
+        .filter(|&op| OPCODES[op as usize].mode != Mode::Relative)
+        .for_each(|op| {
+            let info = OPCODES[op as usize];
+            let extra = if info.page_cross { 1 } else { 0 };
+            assert_eq!(cycles(op, 0xFF), info.cycles + extra, "opcode {:02X}", op);
+        })
+}
+
+#[test]
+fn branch_cycles() {
+    // BNE +0, BNE to the next page, BEQ not taken
+    assert_eq!(cycles(0xD0, 0x00), 3);
+    assert_eq!(cycles(0xD0, 0x80), 4);
+    assert_eq!(cycles(0xF0, 0x7F), 2);
+}
+
+#[test]
+fn interrupt_takes_seven_cycles() {
+    let mut c = CPU::new(FlatMemory::new(&[0x58, 0xEA]));
+    c.init();
+    c.assert_irq();
+    c.execute().unwrap();
+    c.execute().unwrap();
+    assert_eq!(c.execute().unwrap(), 7);
+    assert_eq!(c.total_cycles(), 7 + 2 + 2 + 7);
+}